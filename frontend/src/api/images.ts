@@ -0,0 +1,121 @@
+const API_BASE_URL = import.meta.env.VITE_API_BASE_URL || 'http://localhost:3001';
+
+export interface ImageInfo {
+  id: string;
+  tags: string[];
+  created: number;
+  size: number;
+  containers: number;
+  labels: Record<string, string>;
+  dangling: boolean;
+}
+
+export interface ImageDetail {
+  id: string;
+  tags: string[];
+  digests: string[];
+  created: number;
+  size: number;
+  architecture: string;
+  os: string;
+  author: string;
+  user: string;
+  working_dir: string;
+  entrypoint: string[];
+  cmd: string[];
+  env: string[];
+  exposed_ports: string[];
+  labels: Record<string, string>;
+  layers: number;
+}
+
+export interface ImageLayer {
+  id: string;
+  created: number;
+  created_by: string;
+  tags: string[];
+  size: number;
+  comment: string;
+}
+
+export interface ImageFilters {
+  all?: boolean;
+  reference?: string;
+  dangling?: boolean;
+  label?: string;
+}
+
+export interface ImageRemoval {
+  untagged: string[];
+  deleted: string[];
+  space_reclaimed: number;
+}
+
+// References such as `team/app:1` contain slashes, which the path segment can't hold
+const imageUrl = (id: string) => `${API_BASE_URL}/api/images/${encodeURIComponent(id)}`;
+
+export const imageApi = {
+  async listImages(filters: ImageFilters = {}): Promise<ImageInfo[]> {
+    const params = new URLSearchParams();
+    for (const [key, value] of Object.entries(filters)) {
+      if (value !== undefined) {
+        params.set(key, String(value));
+      }
+    }
+    const response = await fetch(`${API_BASE_URL}/api/images?${params}`);
+    if (!response.ok) {
+      throw new Error('Failed to fetch images');
+    }
+    return response.json();
+  },
+
+  async getImage(id: string): Promise<ImageDetail> {
+    const response = await fetch(imageUrl(id));
+    if (!response.ok) {
+      throw new Error('Failed to fetch image details');
+    }
+    return response.json();
+  },
+
+  async getImageHistory(id: string): Promise<ImageLayer[]> {
+    const response = await fetch(`${imageUrl(id)}/history`);
+    if (!response.ok) {
+      throw new Error('Failed to fetch image history');
+    }
+    return response.json();
+  },
+
+  async tagImage(id: string, tag: string): Promise<void> {
+    const response = await fetch(`${imageUrl(id)}/tag`, {
+      method: 'POST',
+      headers: {
+        'Content-Type': 'application/json',
+      },
+      body: JSON.stringify({ tag }),
+    });
+    if (!response.ok) {
+      throw new Error('Failed to tag image');
+    }
+  },
+
+  async removeImage(id: string, force = false): Promise<ImageRemoval> {
+    const response = await fetch(`${imageUrl(id)}?force=${force}`, {
+      method: 'DELETE',
+    });
+    if (!response.ok) {
+      throw new Error('Failed to remove image');
+    }
+    return response.json();
+  },
+
+  async pruneImages(label?: string): Promise<ImageRemoval> {
+    const query = label ? `?label=${encodeURIComponent(label)}` : '';
+    const response = await fetch(`${API_BASE_URL}/api/images/prune${query}`, {
+      method: 'POST',
+    });
+    if (!response.ok) {
+      throw new Error('Failed to prune images');
+    }
+    return response.json();
+  },
+};
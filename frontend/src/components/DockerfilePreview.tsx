@@ -26,6 +26,7 @@ export const DockerfilePreview: React.FC<DockerfilePreviewProps> = ({ config })
   const [builtImageTag, setBuiltImageTag] = useState<string | null>(null);
   const [running, setRunning] = useState<boolean>(false);
   const [containerId, setContainerId] = useState<string | null>(null);
+  const [sshHostPort, setSshHostPort] = useState<number | null>(null);
   const [containerName, setContainerName] = useState<string>(
     `${sanitizeDockerName(config.name || 'custom-env')}-container`
   );
@@ -40,6 +41,7 @@ export const DockerfilePreview: React.FC<DockerfilePreviewProps> = ({ config })
       setBuiltImageTag(null);
       setRunning(false);
       setContainerId(null);
+      setSshHostPort(null);
       setContainerName(`${sanitizeDockerName(config.name || 'custom-env')}-container`);
 
       try {
@@ -92,17 +94,14 @@ export const DockerfilePreview: React.FC<DockerfilePreviewProps> = ({ config })
     setRunning(true);
     setError(null);
     setContainerId(null);
+    setSshHostPort(null);
 
     try {
-      // Add SSH port mapping if SSH is enabled
-      // Format: { "container_port": "host_port" }
-      const ports = config.ssh?.enabled && config.ssh.port
-        ? { '22': `${config.ssh.port}` }
-        : undefined;
-
-      // Add ROOT_PASSWORD environment variable if SSH is enabled
+      // The server publishes the SSH port from the image label on a free host port
+      // Pass the SSH password to the entrypoint; SSH_PASSWORD when logging in as the dev user
+      const passwordVariable = config.user ? 'SSH_PASSWORD' : 'ROOT_PASSWORD';
       const env = config.ssh?.enabled && config.ssh.password
-        ? [`ROOT_PASSWORD=${config.ssh.password}`]
+        ? [`${passwordVariable}=${config.ssh.password}`]
         : undefined;
 
       // Add timestamp to make container name unique
@@ -110,10 +109,10 @@ export const DockerfilePreview: React.FC<DockerfilePreviewProps> = ({ config })
 
       const result = await apiClient.runContainer(builtImageTag, {
         name: uniqueContainerName,
-        ports,
         env,
       });
       setContainerId(result.container_id);
+      setSshHostPort(result.ssh_port);
     } catch (err) {
       if (err instanceof ApiError) {
         setError(err.message);
@@ -266,6 +265,7 @@ export const DockerfilePreview: React.FC<DockerfilePreviewProps> = ({ config })
           {containerId && (
             <Alert color="green" title="Container Running">
               Container started successfully with ID: {containerId}
+              {sshHostPort && ` (SSH on host port ${sshHostPort})`}
             </Alert>
           )}
 
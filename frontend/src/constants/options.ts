@@ -1,13 +1,22 @@
 export const OS_OPTIONS = [
   { type: 'ubuntu', name: 'Ubuntu', versions: ['20.04', '22.04', '24.04'] },
   { type: 'debian', name: 'Debian', versions: ['bullseye', 'bookworm'] },
-  { type: 'alpine', name: 'Alpine', versions: ['latest'] },
+  { type: 'alpine', name: 'Alpine', versions: ['3.20', '3.21', '3.22', 'latest'] },
+  { type: 'fedora', name: 'Fedora', versions: ['40', '41'] },
+  { type: 'rocky', name: 'Rocky Linux', versions: ['8', '9'] },
+  { type: 'arch', name: 'Arch Linux', versions: ['latest'] },
+  { type: 'opensuse', name: 'openSUSE', versions: ['15.6', 'tumbleweed'] },
 ] as const;
 
 export const LANGUAGE_OPTIONS = [
   { name: 'python', displayName: 'Python', versions: ['3.9', '3.10', '3.11', '3.12'] },
   { name: 'nodejs', displayName: 'Node.js', versions: ['18', '20', '22'] },
   { name: 'rust', displayName: 'Rust', versions: ['stable', 'nightly'] },
+  { name: 'go', displayName: 'Go', versions: ['1.22.10', '1.23.4'] },
+  { name: 'java', displayName: 'Java', versions: ['17', '21'] },
+  { name: 'ruby', displayName: 'Ruby', versions: ['3.2', '3.3'] },
+  { name: 'php', displayName: 'PHP', versions: ['8.2', '8.3'] },
+  { name: 'perl', displayName: 'Perl', versions: ['5.38', '5.40'] },
 ] as const;
 
 export const TOTAL_STEPS = 4;
\ No newline at end of file
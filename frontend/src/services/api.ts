@@ -15,6 +15,46 @@ export interface BuildResponse {
   tag: string;
 }
 
+export type BuildEvent =
+  | { type: 'log'; line: string }
+  | {
+      type: 'progress';
+      id: string | null;
+      status: string;
+      progress: string | null;
+      current: number | null;
+      total: number | null;
+    }
+  | { type: 'step'; name: string; cached: boolean }
+  | { type: 'image_id'; id: string }
+  | { type: 'error'; message: string };
+
+export type PullPolicy = 'always' | 'missing' | 'never';
+
+export interface BuildOptions {
+  no_cache?: boolean;
+  pull?: PullPolicy;
+  build_args?: Record<string, string>;
+  target?: string;
+  platform?: string;
+  labels?: Record<string, string>;
+  extra_tags?: string[];
+}
+
+export type BuildStatus = 'queued' | 'running' | 'succeeded' | 'failed' | 'cancelled';
+
+export interface BuildJob {
+  id: string;
+  tag: string;
+  status: BuildStatus;
+  logs: string[];
+  image_id: string | null;
+  error: string | null;
+  created: number;
+  started: number | null;
+  finished: number | null;
+}
+
 export interface RunOptions {
   name?: string;
   env?: string[];
@@ -23,6 +63,7 @@ export interface RunOptions {
 
 export interface RunResponse {
   container_id: string;
+  ssh_port: number | null;
 }
 
 export class ApiError extends Error {
@@ -35,6 +76,39 @@ export class ApiError extends Error {
   }
 }
 
+/**
+ * Request body for the build endpoints. A context archive (tar, tar.gz or zip)
+ * is sent as a multipart upload alongside the Dockerfile.
+ */
+function buildBody(
+  dockerfile: string,
+  tag: string,
+  context?: Blob,
+  options?: BuildOptions,
+): RequestInit {
+  if (!context) {
+    return {
+      headers: {
+        'Content-Type': 'application/json',
+      },
+      body: JSON.stringify({
+        dockerfile,
+        tag,
+        options,
+      }),
+    };
+  }
+
+  const form = new FormData();
+  form.append('dockerfile', dockerfile);
+  form.append('tag', tag);
+  if (options) {
+    form.append('options', JSON.stringify(options));
+  }
+  form.append('context', context);
+  return { body: form };
+}
+
 export const apiClient = {
   async generateDockerfile(config: EnvironmentConfig): Promise<string> {
     const response = await fetch(`${API_BASE_URL}/api/dockerfile/generate`, {
@@ -51,12 +125,23 @@ export const apiClient = {
         languages: config.languages.map(lang => ({
           name: lang.name,
           version: lang.version,
+          options: lang.options,
         })),
         ssh: config.ssh ? {
           enabled: config.ssh.enabled,
           port: config.ssh.port,
           password: config.ssh.password,
+          authorized_keys: config.ssh.authorized_keys,
+          disable_password_auth: config.ssh.disable_password_auth,
+          hardened: config.ssh.hardened,
         } : undefined,
+        user: config.user,
+        multi_stage: config.multi_stage,
+        build_cache: config.build_cache,
+        packages: config.packages,
+        env: config.env,
+        args: config.args,
+        labels: config.labels,
       }),
     });
 
@@ -71,16 +156,15 @@ export const apiClient = {
     return data.dockerfile;
   },
 
-  async buildImage(dockerfile: string, tag: string): Promise<BuildResponse> {
+  async buildImage(
+    dockerfile: string,
+    tag: string,
+    context?: Blob,
+    options?: BuildOptions,
+  ): Promise<BuildResponse> {
     const response = await fetch(`${API_BASE_URL}/api/containers/build`, {
       method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-      },
-      body: JSON.stringify({
-        dockerfile,
-        tag,
-      }),
+      ...buildBody(dockerfile, tag, context, options),
     });
 
     if (!response.ok) {
@@ -93,6 +177,88 @@ export const apiClient = {
     return await response.json();
   },
 
+  async buildImageStream(
+    dockerfile: string,
+    tag: string,
+    onEvent: (event: BuildEvent) => void,
+    context?: Blob,
+    options?: BuildOptions,
+  ): Promise<void> {
+    const response = await fetch(`${API_BASE_URL}/api/containers/build/stream`, {
+      method: 'POST',
+      ...buildBody(dockerfile, tag, context, options),
+    });
+
+    if (!response.ok || !response.body) {
+      const errorData: ErrorResponse = await response.json().catch(() => ({
+        error: 'Failed to build image',
+      }));
+      throw new ApiError(response.status, errorData.error);
+    }
+
+    // Server-Sent Events are separated by a blank line; keep-alives carry no data
+    const reader = response.body.pipeThrough(new TextDecoderStream()).getReader();
+    let buffer = '';
+    for (;;) {
+      const { value, done } = await reader.read();
+      if (done) break;
+      buffer += value;
+      const messages = buffer.split('\n\n');
+      buffer = messages.pop() ?? '';
+      for (const message of messages) {
+        const data = message
+          .split('\n')
+          .filter((line) => line.startsWith('data:'))
+          .map((line) => line.slice(5).trimStart())
+          .join('\n');
+        if (data) {
+          onEvent(JSON.parse(data));
+        }
+      }
+    }
+  },
+
+  async submitBuild(
+    dockerfile: string,
+    tag: string,
+    context?: Blob,
+    options?: BuildOptions,
+  ): Promise<string> {
+    const response = await fetch(`${API_BASE_URL}/api/builds`, {
+      method: 'POST',
+      ...buildBody(dockerfile, tag, context, options),
+    });
+
+    if (!response.ok) {
+      throw new ApiError(response.status, 'Failed to queue build');
+    }
+
+    const data: { id: string } = await response.json();
+    return data.id;
+  },
+
+  async getBuild(id: string): Promise<BuildJob> {
+    const response = await fetch(`${API_BASE_URL}/api/builds/${id}`);
+
+    if (!response.ok) {
+      throw new ApiError(response.status, await response.text());
+    }
+
+    return await response.json();
+  },
+
+  async cancelBuild(id: string): Promise<BuildJob> {
+    const response = await fetch(`${API_BASE_URL}/api/builds/${id}`, {
+      method: 'DELETE',
+    });
+
+    if (!response.ok) {
+      throw new ApiError(response.status, await response.text());
+    }
+
+    return await response.json();
+  },
+
   async runContainer(image: string, options?: RunOptions): Promise<RunResponse> {
     const response = await fetch(`${API_BASE_URL}/api/containers/run`, {
       method: 'POST',
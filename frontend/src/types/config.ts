@@ -3,20 +3,57 @@ export interface OsConfig {
   version: string;
 }
 
+export interface LanguageOptions {
+  distribution?: string;
+  tools?: string[];
+  extensions?: string[];
+  components?: string[];
+  targets?: string[];
+}
+
 export interface Language {
   name: string;
   version: string;
+  options?: LanguageOptions;
 }
 
 export interface SshConfig {
   enabled: boolean;
   port: number;
   password: string;
+  authorized_keys?: string[];
+  disable_password_auth?: boolean;
+  hardened?: boolean;
+}
+
+export interface DevUser {
+  name: string;
+  uid?: number;
+  gid?: number;
+  sudo?: boolean;
+  shell?: string;
+}
+
+export interface MultiStageConfig {
+  runtime: 'debian-slim' | 'distroless' | 'alpine';
+  build_command?: string;
+  artifacts: string[];
+  command?: string[];
 }
 
+/** One package name, or a name per package manager (apt, apk, dnf, zypper, pacman) */
+export type SystemPackage = string | Partial<Record<string, string>>;
+
 export interface EnvironmentConfig {
   name?: string;
   os: OsConfig;
   languages: Language[];
   ssh?: SshConfig;
+  user?: DevUser;
+  multi_stage?: MultiStageConfig;
+  build_cache?: boolean;
+  packages?: SystemPackage[];
+  env?: Record<string, string>;
+  args?: Record<string, string | null>;
+  labels?: Record<string, string>;
 }
\ No newline at end of file
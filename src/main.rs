@@ -1,4 +1,5 @@
 use axum::{
+    middleware,
     routing::{get, post},
     Router,
 };
@@ -12,8 +13,15 @@ mod models;
 mod routes;
 mod services;
 
+use services::build_jobs::BuildJobs;
 use services::docker_service::DockerService;
 
+/// Builds allowed to run at once unless `MAX_CONCURRENT_BUILDS` says otherwise
+const DEFAULT_MAX_CONCURRENT_BUILDS: usize = 2;
+
+/// Builds allowed to wait for a slot unless `MAX_QUEUED_BUILDS` says otherwise
+const DEFAULT_MAX_QUEUED_BUILDS: usize = 4;
+
 #[tokio::main]
 async fn main() {
     // Initialize tracing
@@ -25,9 +33,22 @@ async fn main() {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    // Initialize Docker service
-    let docker_service =
-        Arc::new(DockerService::new().expect("Failed to connect to Docker daemon"));
+    // The Dockerfile tools work without Docker, so a missing daemon only
+    // disables the routes that need it
+    let docker_service = Arc::new(DockerService::new());
+    if let Err(e) = docker_service.client() {
+        tracing::warn!("{}; container, image and build routes answer 503", e);
+    }
+    let requires_docker =
+        middleware::from_fn_with_state(docker_service.clone(), routes::require_docker);
+
+    // Background builds beyond the limit wait in a queue, which is bounded
+    // too since every queued build keeps its context in memory
+    let build_jobs = Arc::new(BuildJobs::new(
+        docker_service.clone(),
+        env_limit("MAX_CONCURRENT_BUILDS", DEFAULT_MAX_CONCURRENT_BUILDS),
+        env_limit("MAX_QUEUED_BUILDS", DEFAULT_MAX_QUEUED_BUILDS),
+    ));
 
     // Build our application with routes
     let app = Router::new()
@@ -36,7 +57,29 @@ async fn main() {
             "/api/dockerfile/generate",
             post(routes::dockerfile::generate_dockerfile),
         )
-        .merge(routes::container::container_routes().with_state(docker_service.clone()))
+        .route(
+            "/api/dockerfile/parse",
+            post(routes::dockerfile::parse_dockerfile),
+        )
+        .route(
+            "/api/dockerfile/lint",
+            post(routes::dockerfile::lint_dockerfile),
+        )
+        .merge(
+            routes::container::container_routes()
+                .with_state(docker_service.clone())
+                .route_layer(requires_docker.clone()),
+        )
+        .merge(
+            routes::image::image_routes()
+                .with_state(docker_service.clone())
+                .route_layer(requires_docker.clone()),
+        )
+        .merge(
+            routes::build::build_routes()
+                .with_state(build_jobs)
+                .route_layer(requires_docker),
+        )
         .layer(CorsLayer::permissive());
 
     // Run it with hyper on 0.0.0.0:3001
@@ -46,3 +89,12 @@ async fn main() {
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
+
+/// A positive limit from the environment, `default` when unset or invalid
+fn env_limit(name: &str, default: usize) -> usize {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .filter(|&limit| limit > 0)
+        .unwrap_or(default)
+}
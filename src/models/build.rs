@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+/// A single update from a running image build, forwarded to clients as it
+/// arrives from the Docker daemon
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BuildEvent {
+    /// Output of a build step
+    Log { line: String },
+    /// Progress of a long-running operation such as a layer download
+    Progress {
+        id: Option<String>,
+        status: String,
+        progress: Option<String>,
+        current: Option<i64>,
+        total: Option<i64>,
+    },
+    /// A BuildKit step finished, possibly straight from the cache
+    Step { name: String, cached: bool },
+    /// ID of the image that was built
+    ImageId { id: String },
+    /// The build failed; no further events follow
+    Error { message: String },
+}
+
+impl BuildEvent {
+    /// Line this event contributes to a plain-text build log
+    pub fn log_line(&self) -> Option<String> {
+        match self {
+            BuildEvent::Log { line } => Some(line.clone()),
+            BuildEvent::Step { name, cached } => {
+                let prefix = if *cached { "CACHED " } else { "" };
+                Some(format!("{}{}\n", prefix, name))
+            }
+            BuildEvent::Progress { .. } | BuildEvent::ImageId { .. } | BuildEvent::Error { .. } => {
+                None
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl BuildStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(
+            self,
+            BuildStatus::Succeeded | BuildStatus::Failed | BuildStatus::Cancelled
+        )
+    }
+}
+
+/// An image build running in the background, with the latest lines of its log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildJob {
+    pub id: String,
+    pub tag: String,
+    pub status: BuildStatus,
+    pub logs: VecDeque<String>,
+    pub image_id: Option<String>,
+    pub error: Option<String>,
+    pub created: i64,
+    pub started: Option<i64>,
+    pub finished: Option<i64>,
+}
+
+/// When the daemon pulls base images during a build
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PullPolicy {
+    /// Always check the registry for a newer base image
+    #[default]
+    #[serde(alias = "on")]
+    Always,
+    /// Only pull base images that are not available locally
+    Missing,
+    /// Never contact a registry; every base image must already be present
+    #[serde(alias = "off")]
+    Never,
+}
+
+/// Options for an image build beyond the Dockerfile and tag
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BuildOptions {
+    pub no_cache: bool,
+    pub pull: PullPolicy,
+    pub build_args: BTreeMap<String, String>,
+    /// Stage of a multi-stage Dockerfile to stop at
+    pub target: Option<String>,
+    /// Target platform such as `linux/arm64`
+    pub platform: Option<String>,
+    pub labels: BTreeMap<String, String>,
+    /// Tags applied to the image in addition to the main one
+    pub extra_tags: Vec<String>,
+}
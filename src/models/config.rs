@@ -1,28 +1,134 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct OsConfig {
     pub os_type: String,
     pub version: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct LanguageOptions {
+    /// Runtime vendor or flavour, e.g. `temurin` or `openjdk` for Java
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distribution: Option<String>,
+    /// Extra tools installed with the runtime, e.g. `maven` or `gradle@8.10.2`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+    /// Runtime extensions, e.g. `mbstring` or `intl` for PHP
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<String>,
+    /// Toolchain components, e.g. `clippy` or `rust-src` for Rust
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<String>,
+    /// Extra compilation targets, e.g. `wasm32-unknown-unknown` for Rust
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Language {
     pub name: String,
     pub version: String,
+    #[serde(default)]
+    pub options: LanguageOptions,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct SshConfig {
     pub enabled: bool,
     pub port: u16,
     pub password: String,
+    /// Public keys (`ssh-ed25519 AAAA... comment`) allowed to log in
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authorized_keys: Vec<String>,
+    /// Only accept key-based logins
+    #[serde(default)]
+    pub disable_password_auth: bool,
+    /// Restrict sshd to the login user with tighter auth limits, no agent or
+    /// X11 forwarding and idle session timeouts
+    #[serde(default)]
+    pub hardened: bool,
+}
+
+/// Non-root account that owns `/app` and runs the development image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DevUser {
+    pub name: String,
+    /// Should match the host user's UID so bind-mounted files keep their owner
+    #[serde(default = "default_user_id")]
+    pub uid: u32,
+    #[serde(default = "default_user_id")]
+    pub gid: u32,
+    /// Grant passwordless sudo
+    #[serde(default)]
+    pub sudo: bool,
+    /// Login shell, defaults to bash (`/bin/sh` on Alpine)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+}
+
+fn default_user_id() -> u32 {
+    1000
+}
+
+/// Base image of the runtime stage in a multi-stage build
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuntimeImage {
+    DebianSlim,
+    Distroless,
+    Alpine,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+pub struct MultiStageConfig {
+    pub runtime: RuntimeImage,
+    /// Shell command run in the builder stage after the sources are copied
+    pub build_command: Option<String>,
+    /// Paths in the builder stage copied into the runtime stage's /app
+    pub artifacts: Vec<String>,
+    /// Exec-form command of the runtime image
+    #[serde(default)]
+    pub command: Vec<String>,
+}
+
+/// Extra distro package, either one name for every package manager or a
+/// name per package manager (`apt`, `apk`, `dnf`, `zypper`, `pacman`).
+/// A per-manager package is skipped on package managers it doesn't name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SystemPackage {
+    Name(String),
+    PerManager(BTreeMap<String, String>),
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct EnvironmentConfig {
     pub name: Option<String>,
     pub os: OsConfig,
     pub languages: Vec<Language>,
     pub ssh: Option<SshConfig>,
+    /// Create this user and run the image as it instead of root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<DevUser>,
+    /// Emit a builder stage with the toolchains and a slim runtime stage
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multi_stage: Option<MultiStageConfig>,
+    /// Mount package manager and language caches with BuildKit so rebuilds
+    /// don't download everything again
+    #[serde(default)]
+    pub build_cache: bool,
+    /// Extra distro packages, e.g. `git` or `jq`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<SystemPackage>,
+    /// ENV instructions of the final image
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// ARG instructions, with optional default values
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub args: BTreeMap<String, Option<String>>,
+    /// LABEL instructions of the final image
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
 }
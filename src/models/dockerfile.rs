@@ -0,0 +1,370 @@
+//! Typed Dockerfile model and its renderer.
+
+use std::fmt;
+
+/// A Dockerfile as a list of sections separated by blank lines
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Dockerfile {
+    pub sections: Vec<Section>,
+}
+
+impl Dockerfile {
+    pub fn push(&mut self, section: Section) {
+        self.sections.push(section);
+    }
+
+    pub fn render(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for Dockerfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, section) in self.sections.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n\n")?;
+            }
+            write!(f, "{}", section)?;
+        }
+        Ok(())
+    }
+}
+
+/// Comment lines followed by the instructions they describe
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Section {
+    pub comments: Vec<String>,
+    pub instructions: Vec<Instruction>,
+}
+
+impl Section {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comments.push(comment.into());
+        self
+    }
+
+    pub fn instruction(mut self, instruction: Instruction) -> Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    pub fn instructions(mut self, instructions: impl IntoIterator<Item = Instruction>) -> Self {
+        self.instructions.extend(instructions);
+        self
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let comments = self.comments.iter().map(|c| format!("# {}", c));
+        let instructions = self.instructions.iter().map(|i| i.to_string());
+        let lines: Vec<String> = comments.chain(instructions).collect();
+        f.write_str(&lines.join("\n"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    From {
+        image: String,
+        alias: Option<String>,
+    },
+    /// Shell commands chained with `&&`, one per line
+    Run {
+        mounts: Vec<CacheMount>,
+        commands: Vec<String>,
+    },
+    Env(String, String),
+    /// Build argument with an optional default
+    Arg(String, Option<String>),
+    Label(String, String),
+    Workdir(String),
+    Copy {
+        from: Option<String>,
+        sources: Vec<String>,
+        destination: String,
+    },
+    User(String),
+    Expose(u16),
+    /// Exec form
+    Entrypoint(Vec<String>),
+    /// Exec form
+    Cmd(Vec<String>),
+}
+
+impl Instruction {
+    pub fn run(commands: Vec<String>) -> Self {
+        Instruction::Run {
+            mounts: Vec::new(),
+            commands,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::From { image, alias } => {
+                write!(f, "FROM {}", image)?;
+                if let Some(alias) = alias {
+                    write!(f, " AS {}", alias)?;
+                }
+                Ok(())
+            }
+            Instruction::Run { mounts, commands } => {
+                f.write_str("RUN ")?;
+                for mount in mounts {
+                    write!(f, "{} \\\n    ", mount)?;
+                }
+                f.write_str(&commands.join(" && \\\n    "))
+            }
+            Instruction::Env(key, value) => write!(f, "ENV {}={}", key, double_quote(value)),
+            Instruction::Arg(name, None) => write!(f, "ARG {}", name),
+            Instruction::Arg(name, Some(value)) => {
+                write!(f, "ARG {}={}", name, double_quote(value))
+            }
+            Instruction::Label(key, value) => write!(f, "LABEL {}={}", key, double_quote(value)),
+            Instruction::Workdir(path) => write!(f, "WORKDIR {}", path),
+            Instruction::Copy {
+                from,
+                sources,
+                destination,
+            } => {
+                f.write_str("COPY ")?;
+                if let Some(from) = from {
+                    write!(f, "--from={} ", from)?;
+                }
+                let mut paths = sources.clone();
+                paths.push(destination.clone());
+                // The JSON form is the only way to copy paths with whitespace
+                if paths.iter().all(|p| is_plain(p)) {
+                    f.write_str(&paths.join(" "))
+                } else {
+                    f.write_str(&exec_form(&paths))
+                }
+            }
+            Instruction::User(user) => write!(f, "USER {}", user),
+            Instruction::Expose(port) => write!(f, "EXPOSE {}", port),
+            Instruction::Entrypoint(args) => write!(f, "ENTRYPOINT {}", exec_form(args)),
+            Instruction::Cmd(args) => write!(f, "CMD {}", exec_form(args)),
+        }
+    }
+}
+
+/// BuildKit cache directory mounted into a RUN instruction
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheMount {
+    pub target: String,
+    /// Serialize concurrent builds for caches that tolerate only one writer
+    pub locked: bool,
+}
+
+impl CacheMount {
+    pub fn new(target: impl Into<String>) -> Self {
+        Self {
+            target: target.into(),
+            locked: false,
+        }
+    }
+
+    pub fn locked(target: impl Into<String>) -> Self {
+        Self {
+            locked: true,
+            ..Self::new(target)
+        }
+    }
+}
+
+impl fmt::Display for CacheMount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "--mount=type=cache,target={}", self.target)?;
+        if self.locked {
+            f.write_str(",sharing=locked")?;
+        }
+        Ok(())
+    }
+}
+
+/// True for values that are safe unquoted in shell commands, image
+/// references and URLs, e.g. versions like `3.12`, `1.83.0` or `bookworm`
+pub fn is_plain_word(value: &str) -> bool {
+    value.len() <= 128
+        && value
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphanumeric())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '+' | '@'))
+}
+
+/// True for names usable as ENV and ARG variables
+pub fn is_variable_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// True for label keys in the usual reverse-DNS style, e.g. `org.opencontainers.image.source`
+pub fn is_label_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '/'))
+}
+
+/// Quote `value` as a single shell word
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Shell command that prints each of `lines` verbatim
+pub fn print_lines(lines: &[String]) -> String {
+    let lines: Vec<String> = lines.iter().map(|line| shell_quote(line)).collect();
+    format!("printf '%s\\n' {}", lines.join(" "))
+}
+
+/// Shell command that writes `lines` to `path`
+pub fn write_file(path: &str, lines: &[String]) -> String {
+    format!("{} > {}", print_lines(lines), path)
+}
+
+/// Double-quoted ENV/LABEL value; `$` is left alone so `${PATH}` still expands
+fn double_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn exec_form(args: &[String]) -> String {
+    let args: Vec<String> = args
+        .iter()
+        .map(|arg| serde_json::to_string(arg).expect("strings serialize"))
+        .collect();
+    format!("[{}]", args.join(", "))
+}
+
+fn is_plain(path: &str) -> bool {
+    !path.is_empty()
+        && !path
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | '[' | ']'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_continuation_lines() {
+        let run = Instruction::run(vec!["apt-get update".to_string(), "true".to_string()]);
+        assert_eq!(run.to_string(), "RUN apt-get update && \\\n    true");
+    }
+
+    #[test]
+    fn test_run_cache_mounts() {
+        let run = Instruction::Run {
+            mounts: vec![
+                CacheMount::locked("/var/cache/apt"),
+                CacheMount::new("/root/.cache/pip"),
+            ],
+            commands: vec!["apt-get update".to_string()],
+        };
+        assert_eq!(
+            run.to_string(),
+            "RUN --mount=type=cache,target=/var/cache/apt,sharing=locked \\\n    \
+             --mount=type=cache,target=/root/.cache/pip \\\n    apt-get update"
+        );
+    }
+
+    #[test]
+    fn test_exec_form_and_copy_quoting() {
+        let cmd = Instruction::Cmd(vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            "echo \"hi\"".to_string(),
+        ]);
+        assert_eq!(cmd.to_string(), r#"CMD ["/bin/sh", "-c", "echo \"hi\""]"#);
+
+        let copy = Instruction::Copy {
+            from: Some("builder".to_string()),
+            sources: vec!["target/release/app".to_string()],
+            destination: "/app/app".to_string(),
+        };
+        assert_eq!(
+            copy.to_string(),
+            "COPY --from=builder target/release/app /app/app"
+        );
+
+        let copy = Instruction::Copy {
+            from: None,
+            sources: vec!["my file".to_string()],
+            destination: "/app/".to_string(),
+        };
+        assert_eq!(copy.to_string(), r#"COPY ["my file", "/app/"]"#);
+    }
+
+    #[test]
+    fn test_env_quoting() {
+        let env = Instruction::Env("GREETING".to_string(), "say \"hi\" to ${USER}".to_string());
+        assert_eq!(env.to_string(), r#"ENV GREETING="say \"hi\" to ${USER}""#);
+
+        let arg = Instruction::Arg("VERSION".to_string(), Some("1.0".to_string()));
+        assert_eq!(arg.to_string(), r#"ARG VERSION="1.0""#);
+        assert_eq!(
+            Instruction::Arg("VERSION".to_string(), None).to_string(),
+            "ARG VERSION"
+        );
+    }
+
+    #[test]
+    fn test_plain_words() {
+        for word in [
+            "3.12",
+            "bookworm",
+            "1.83.0",
+            "gradle@8.10.2",
+            "wasm32-unknown-unknown",
+        ] {
+            assert!(is_plain_word(word), "{}", word);
+        }
+        for word in [
+            "", "-y", "3.12; id", "$(id)", "a b", "a\nb", "../x", "x'y", "`id`",
+        ] {
+            assert!(!is_plain_word(word), "{:?}", word);
+        }
+    }
+
+    #[test]
+    fn test_write_file_quotes_lines() {
+        let command = write_file(
+            "/entrypoint.sh",
+            &["#!/bin/sh".to_string(), "echo 'hi'".to_string()],
+        );
+        assert_eq!(
+            command,
+            r#"printf '%s\n' '#!/bin/sh' 'echo '\''hi'\''' > /entrypoint.sh"#
+        );
+    }
+
+    #[test]
+    fn test_sections_are_separated_by_blank_lines() {
+        let mut dockerfile = Dockerfile::default();
+        dockerfile.push(Section::new().instruction(Instruction::From {
+            image: "debian:bookworm".to_string(),
+            alias: Some("builder".to_string()),
+        }));
+        dockerfile.push(
+            Section::new()
+                .comment("Set default command")
+                .instruction(Instruction::Cmd(vec!["/bin/bash".to_string()])),
+        );
+        assert_eq!(
+            dockerfile.render(),
+            "FROM debian:bookworm AS builder\n\n# Set default command\nCMD [\"/bin/bash\"]"
+        );
+    }
+}
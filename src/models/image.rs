@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageInfo {
+    pub id: String,
+    pub tags: Vec<String>,
+    pub created: i64,
+    pub size: i64,
+    /// Containers using the image, or -1 when the daemon didn't count them
+    pub containers: i64,
+    pub labels: HashMap<String, String>,
+    /// Untagged image, left behind when its tag moved to a newer build
+    pub dangling: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageDetail {
+    pub id: String,
+    pub tags: Vec<String>,
+    pub digests: Vec<String>,
+    pub created: i64,
+    pub size: i64,
+    pub architecture: String,
+    pub os: String,
+    pub author: String,
+    pub user: String,
+    pub working_dir: String,
+    pub entrypoint: Vec<String>,
+    pub cmd: Vec<String>,
+    pub env: Vec<String>,
+    pub exposed_ports: Vec<String>,
+    pub labels: HashMap<String, String>,
+    pub layers: usize,
+}
+
+/// One entry of an image's history, newest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageLayer {
+    pub id: String,
+    pub created: i64,
+    pub created_by: String,
+    pub tags: Vec<String>,
+    pub size: i64,
+    pub comment: String,
+}
+
+/// Filters for listing images; all of them must match
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImageFilters {
+    /// Include intermediate images
+    #[serde(default)]
+    pub all: bool,
+    /// Reference pattern such as `containerhelper*` or `app:1.*`
+    pub reference: Option<String>,
+    pub dangling: Option<bool>,
+    /// `key` or `key=value`
+    pub label: Option<String>,
+}
+
+/// Tags removed and images deleted by a remove or prune
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImageRemoval {
+    pub untagged: Vec<String>,
+    pub deleted: Vec<String>,
+    pub space_reclaimed: i64,
+}
@@ -1,2 +1,5 @@
+pub mod build;
 pub mod config;
 pub mod container;
+pub mod dockerfile;
+pub mod image;
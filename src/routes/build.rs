@@ -0,0 +1,72 @@
+use crate::models::build::BuildJob;
+use crate::routes::container::BuildUpload;
+use crate::services::build_context::MAX_CONTEXT_SIZE;
+use crate::services::build_jobs::{BuildJobError, BuildJobs};
+use axum::{
+    extract::{DefaultBodyLimit, Path, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use serde::Serialize;
+use std::sync::Arc;
+
+#[derive(Debug, Serialize)]
+pub struct SubmitResponse {
+    pub id: String,
+}
+
+pub fn build_routes() -> Router<Arc<BuildJobs>> {
+    Router::new()
+        .route(
+            "/api/builds",
+            get(list_builds)
+                .post(submit_build)
+                .layer(DefaultBodyLimit::max(MAX_CONTEXT_SIZE as usize)),
+        )
+        .route("/api/builds/:id", get(get_build).delete(cancel_build))
+}
+
+async fn submit_build(
+    State(build_jobs): State<Arc<BuildJobs>>,
+    upload: BuildUpload,
+) -> Result<(StatusCode, Json<SubmitResponse>), (StatusCode, String)> {
+    let (context, tag, options) = upload.prepare().await?;
+    let id = build_jobs
+        .submit(context, tag, options)
+        .map_err(error_response)?;
+    tracing::info!("Queued build {}", id);
+    Ok((StatusCode::ACCEPTED, Json(SubmitResponse { id })))
+}
+
+async fn list_builds(State(build_jobs): State<Arc<BuildJobs>>) -> Json<Vec<BuildJob>> {
+    Json(build_jobs.list())
+}
+
+async fn get_build(
+    State(build_jobs): State<Arc<BuildJobs>>,
+    Path(id): Path<String>,
+) -> Result<Json<BuildJob>, (StatusCode, String)> {
+    build_jobs
+        .get(&id)
+        .map(Json)
+        .ok_or_else(|| error_response(BuildJobError::NotFound(id)))
+}
+
+async fn cancel_build(
+    State(build_jobs): State<Arc<BuildJobs>>,
+    Path(id): Path<String>,
+) -> Result<Json<BuildJob>, (StatusCode, String)> {
+    let job = build_jobs.cancel(&id).map_err(error_response)?;
+    tracing::info!("Cancelled build {}", id);
+    Ok(Json(job))
+}
+
+fn error_response(error: BuildJobError) -> (StatusCode, String) {
+    let status = match error {
+        BuildJobError::NotFound(_) => StatusCode::NOT_FOUND,
+        BuildJobError::Finished(_) => StatusCode::CONFLICT,
+        BuildJobError::QueueFull(_) => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, error.to_string())
+}
@@ -1,12 +1,20 @@
+use crate::models::build::BuildOptions;
 use crate::models::container::{ContainerDetail, ContainerInfo};
+use crate::services::build_context::{BuildContext, ContextError, ContextSource, MAX_CONTEXT_SIZE};
+use crate::services::build_options;
 use crate::services::docker_service::DockerService;
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
+    async_trait,
+    extract::{DefaultBodyLimit, FromRequest, Multipart, Path, Request, State},
+    http::{header, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
     routing::{get, post},
     Json, Router,
 };
+use futures_util::stream::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -15,6 +23,137 @@ use std::sync::Arc;
 pub struct BuildRequest {
     pub dockerfile: String,
     pub tag: String,
+    /// Server-side directory to use as the build context
+    pub context_path: Option<String>,
+    #[serde(default)]
+    pub options: BuildOptions,
+}
+
+/// Body of the build endpoints: a JSON `BuildRequest`, or a multipart form
+/// with the same fields, `options` as JSON, plus an optional `context` tar or
+/// zip archive
+#[derive(Debug)]
+pub struct BuildUpload {
+    pub request: BuildRequest,
+    pub archive: Option<Vec<u8>>,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequest<S> for BuildUpload {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let multipart_form = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("multipart/form-data"));
+        if !multipart_form {
+            let Json(request) = Json::<BuildRequest>::from_request(req, state)
+                .await
+                .map_err(|e| (e.status(), e.body_text()))?;
+            return Ok(Self {
+                request,
+                archive: None,
+            });
+        }
+
+        let mut multipart = Multipart::from_request(req, state)
+            .await
+            .map_err(|e| (e.status(), e.body_text()))?;
+        let (mut dockerfile, mut tag, mut context_path, mut archive) = (None, None, None, None);
+        let mut options = BuildOptions::default();
+        while let Some(field) = multipart
+            .next_field()
+            .await
+            .map_err(|e| (e.status(), e.body_text()))?
+        {
+            let name = field.name().unwrap_or_default().to_string();
+            match name.as_str() {
+                "context" => {
+                    let data = field
+                        .bytes()
+                        .await
+                        .map_err(|e| (e.status(), e.body_text()))?;
+                    archive = Some(data.to_vec());
+                }
+                "dockerfile" | "tag" | "context_path" | "options" => {
+                    let text = field
+                        .text()
+                        .await
+                        .map_err(|e| (e.status(), e.body_text()))?;
+                    match name.as_str() {
+                        "dockerfile" => dockerfile = Some(text),
+                        "tag" => tag = Some(text),
+                        "context_path" => context_path = Some(text),
+                        _ => {
+                            options = serde_json::from_str(&text).map_err(|e| {
+                                (StatusCode::BAD_REQUEST, format!("Invalid options: {}", e))
+                            })?
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let missing = |field: &str| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Missing field `{}`", field),
+            )
+        };
+        Ok(Self {
+            request: BuildRequest {
+                dockerfile: dockerfile.ok_or_else(|| missing("dockerfile"))?,
+                tag: tag.ok_or_else(|| missing("tag"))?,
+                context_path,
+                options,
+            },
+            archive,
+        })
+    }
+}
+
+impl BuildUpload {
+    /// Validate the options and assemble the build context. The context is
+    /// built off the async runtime, since it may read and compress a large
+    /// amount of data.
+    pub async fn prepare(
+        self,
+    ) -> Result<(BuildContext, String, BuildOptions), (StatusCode, String)> {
+        let BuildUpload { request, archive } = self;
+        build_options::validate(&request.options, &request.tag, &request.dockerfile)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+        let source = match (archive, request.context_path) {
+            (Some(_), Some(_)) => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "Send either a context archive or a context_path, not both".to_string(),
+                ))
+            }
+            (Some(archive), None) => Some(ContextSource::Archive(archive)),
+            (None, Some(path)) => Some(ContextSource::Directory(path.into())),
+            (None, None) => None,
+        };
+
+        let dockerfile = request.dockerfile;
+        let context = tokio::task::spawn_blocking(move || {
+            BuildContext::new(&dockerfile, source.as_ref(), MAX_CONTEXT_SIZE)
+        })
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| {
+            let status = match e {
+                ContextError::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+                ContextError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                _ => StatusCode::BAD_REQUEST,
+            };
+            (status, e.to_string())
+        })?;
+        Ok((context, request.tag, request.options))
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -34,13 +173,22 @@ pub struct RunRequest {
 #[derive(Debug, Serialize)]
 pub struct RunResponse {
     pub container_id: String,
+    /// Host port sshd is reachable on, for images with SSH enabled
+    pub ssh_port: Option<u16>,
 }
 
 pub fn container_routes() -> Router<Arc<DockerService>> {
     Router::new()
         .route("/api/containers", get(list_containers))
         // Specific routes must come before parameterized routes
-        .route("/api/containers/build", post(build_image))
+        .route(
+            "/api/containers/build",
+            post(build_image).layer(DefaultBodyLimit::max(MAX_CONTEXT_SIZE as usize)),
+        )
+        .route(
+            "/api/containers/build/stream",
+            post(build_image_stream).layer(DefaultBodyLimit::max(MAX_CONTEXT_SIZE as usize)),
+        )
         .route("/api/containers/run", post(run_container))
         .route(
             "/api/containers/:id",
@@ -131,18 +279,13 @@ async fn remove_container(
 
 async fn build_image(
     State(docker_service): State<Arc<DockerService>>,
-    Json(request): Json<BuildRequest>,
+    upload: BuildUpload,
 ) -> Result<Json<BuildResponse>, (StatusCode, String)> {
-    tracing::info!("Building image with tag: {}", request.tag);
+    tracing::info!("Building image with tag: {}", upload.request.tag);
+    let (context, tag, options) = upload.prepare().await?;
 
-    match docker_service
-        .build_image(&request.dockerfile, &request.tag)
-        .await
-    {
-        Ok(logs) => Ok(Json(BuildResponse {
-            logs,
-            tag: request.tag,
-        })),
+    match docker_service.build_image(context, &tag, options).await {
+        Ok(logs) => Ok(Json(BuildResponse { logs, tag })),
         Err(e) => {
             tracing::error!("Failed to build image: {}", e);
             Err((
@@ -153,6 +296,22 @@ async fn build_image(
     }
 }
 
+/// Build an image and forward each build event to the client as a
+/// Server-Sent Event while the build is running
+async fn build_image_stream(
+    State(docker_service): State<Arc<DockerService>>,
+    upload: BuildUpload,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, (StatusCode, String)> {
+    tracing::info!("Streaming build of image with tag: {}", upload.request.tag);
+    let (context, tag, options) = upload.prepare().await?;
+
+    let events = docker_service.build_image_stream(context, &tag, options);
+    Ok(
+        Sse::new(events.map(|event| Event::default().json_data(event)))
+            .keep_alive(KeepAlive::default()),
+    )
+}
+
 async fn run_container(
     State(docker_service): State<Arc<DockerService>>,
     Json(request): Json<RunRequest>,
@@ -168,7 +327,10 @@ async fn run_container(
         )
         .await
     {
-        Ok(container_id) => Ok(Json(RunResponse { container_id })),
+        Ok((container_id, ssh_port)) => Ok(Json(RunResponse {
+            container_id,
+            ssh_port,
+        })),
         Err(e) => {
             tracing::error!("Failed to run container: {}", e);
             Err((
@@ -3,16 +3,28 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::models::config::EnvironmentConfig;
-use crate::services::dockerfile_generator;
+use crate::services::dockerfile_generator::{self, GeneratorError};
+use crate::services::dockerfile_linter::{self, LintFinding};
+use crate::services::dockerfile_parser::{self, ImportedDockerfile, ParseError};
 
 #[derive(Debug, Serialize)]
 pub struct DockerfileResponse {
     pub dockerfile: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct DockerfileRequest {
+    pub dockerfile: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LintResponse {
+    pub findings: Vec<LintFinding>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
     pub error: String,
@@ -41,13 +53,40 @@ pub async fn generate_dockerfile(
     }
 
     // Generate the Dockerfile
-    let dockerfile = dockerfile_generator::generate_dockerfile(&config);
+    let dockerfile = dockerfile_generator::generate_dockerfile(&config)?;
 
     tracing::debug!("Generated Dockerfile:\n{}", dockerfile);
 
     Ok(Json(DockerfileResponse { dockerfile }))
 }
 
+/// POST /api/dockerfile/parse
+/// Map an existing Dockerfile onto a configuration and report what could not be mapped
+pub async fn parse_dockerfile(
+    Json(request): Json<DockerfileRequest>,
+) -> Result<Json<ImportedDockerfile>, AppError> {
+    let imported = dockerfile_parser::import_dockerfile(&request.dockerfile)?;
+
+    tracing::debug!(
+        "Imported Dockerfile with {} unmapped instructions",
+        imported.unmapped.len()
+    );
+
+    Ok(Json(imported))
+}
+
+/// POST /api/dockerfile/lint
+/// Check a Dockerfile against best-practice rules
+pub async fn lint_dockerfile(
+    Json(request): Json<DockerfileRequest>,
+) -> Result<Json<LintResponse>, AppError> {
+    let findings = dockerfile_linter::lint(&request.dockerfile)?;
+
+    tracing::debug!("Lint reported {} findings", findings.len());
+
+    Ok(Json(LintResponse { findings }))
+}
+
 // Custom error type for better error handling
 #[derive(Debug)]
 pub enum AppError {
@@ -65,3 +104,15 @@ impl IntoResponse for AppError {
         (status, body).into_response()
     }
 }
+
+impl From<GeneratorError> for AppError {
+    fn from(err: GeneratorError) -> Self {
+        AppError::BadRequest(err.to_string())
+    }
+}
+
+impl From<ParseError> for AppError {
+    fn from(err: ParseError) -> Self {
+        AppError::BadRequest(err.to_string())
+    }
+}
@@ -0,0 +1,142 @@
+use crate::models::image::{ImageDetail, ImageFilters, ImageInfo, ImageLayer, ImageRemoval};
+use crate::services::build_options::is_image_reference;
+use crate::services::docker_service::DockerService;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize)]
+pub struct TagRequest {
+    /// New reference for the image, e.g. `registry.example.com/app:1.2`
+    pub tag: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RemoveQuery {
+    /// Remove the image even if it is tagged more than once or used by a stopped container
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PruneQuery {
+    /// Only prune images with this label, `key` or `key=value`
+    pub label: Option<String>,
+}
+
+/// Images are addressed by ID or by reference; references containing `/`
+/// must be percent-encoded
+pub fn image_routes() -> Router<Arc<DockerService>> {
+    Router::new()
+        .route("/api/images", get(list_images))
+        // Specific routes must come before parameterized routes
+        .route("/api/images/prune", post(prune_images))
+        .route("/api/images/:id", get(get_image).delete(remove_image))
+        .route("/api/images/:id/history", get(image_history))
+        .route("/api/images/:id/tag", post(tag_image))
+}
+
+/// Status for a failed Docker call, passing through the daemon's "not found"
+/// and "conflict" answers so clients can tell them apart
+fn error_response(error: anyhow::Error, action: &str) -> (StatusCode, String) {
+    let status = match error.downcast_ref::<bollard::errors::Error>() {
+        Some(bollard::errors::Error::DockerResponseServerError { status_code, .. }) => {
+            match *status_code {
+                404 => StatusCode::NOT_FOUND,
+                409 => StatusCode::CONFLICT,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    tracing::error!("Failed to {}: {}", action, error);
+    (status, format!("Failed to {}: {}", action, error))
+}
+
+async fn list_images(
+    State(docker_service): State<Arc<DockerService>>,
+    Query(filters): Query<ImageFilters>,
+) -> Result<Json<Vec<ImageInfo>>, (StatusCode, String)> {
+    docker_service
+        .list_images(&filters)
+        .await
+        .map(Json)
+        .map_err(|e| error_response(e, "list images"))
+}
+
+async fn get_image(
+    State(docker_service): State<Arc<DockerService>>,
+    Path(id): Path<String>,
+) -> Result<Json<ImageDetail>, (StatusCode, String)> {
+    docker_service
+        .get_image(&id)
+        .await
+        .map(Json)
+        .map_err(|e| error_response(e, "get image"))
+}
+
+async fn image_history(
+    State(docker_service): State<Arc<DockerService>>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<ImageLayer>>, (StatusCode, String)> {
+    docker_service
+        .image_history(&id)
+        .await
+        .map(Json)
+        .map_err(|e| error_response(e, "get image history"))
+}
+
+async fn tag_image(
+    State(docker_service): State<Arc<DockerService>>,
+    Path(id): Path<String>,
+    Json(request): Json<TagRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if !is_image_reference(&request.tag) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Invalid image tag: {}", request.tag),
+        ));
+    }
+
+    tracing::info!("Tagging image {} as {}", id, request.tag);
+    docker_service
+        .tag_image(&id, &request.tag)
+        .await
+        .map(|_| StatusCode::CREATED)
+        .map_err(|e| error_response(e, "tag image"))
+}
+
+async fn remove_image(
+    State(docker_service): State<Arc<DockerService>>,
+    Path(id): Path<String>,
+    Query(query): Query<RemoveQuery>,
+) -> Result<Json<ImageRemoval>, (StatusCode, String)> {
+    tracing::info!("Removing image {}", id);
+    docker_service
+        .remove_image(&id, query.force)
+        .await
+        .map(Json)
+        .map_err(|e| error_response(e, "remove image"))
+}
+
+async fn prune_images(
+    State(docker_service): State<Arc<DockerService>>,
+    Query(query): Query<PruneQuery>,
+) -> Result<Json<ImageRemoval>, (StatusCode, String)> {
+    let removal = docker_service
+        .prune_images(query.label.as_deref())
+        .await
+        .map_err(|e| error_response(e, "prune images"))?;
+
+    tracing::info!(
+        "Pruned {} images, reclaiming {} bytes",
+        removal.deleted.len(),
+        removal.space_reclaimed
+    );
+    Ok(Json(removal))
+}
@@ -1,3 +1,27 @@
+pub mod build;
 pub mod container;
 pub mod dockerfile;
 pub mod health;
+pub mod image;
+
+use crate::services::docker_service::DockerService;
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+/// Answer requests to Docker-backed routes with 503 while no daemon is reachable
+pub async fn require_docker(
+    State(docker_service): State<Arc<DockerService>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Err(e) = docker_service.client() {
+        tracing::warn!("{}", e);
+        return (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response();
+    }
+    next.run(request).await
+}
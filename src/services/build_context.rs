@@ -0,0 +1,635 @@
+//! Build contexts sent to the Docker daemon: the generated Dockerfile plus
+//! the project files uploaded with it or read from a server-side directory.
+
+use regex::Regex;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Largest context accepted, for both the upload and the files unpacked from it
+pub const MAX_CONTEXT_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Directory that server-side contexts must live in; unset disables them
+const CONTEXT_ROOT_VAR: &str = "BUILD_CONTEXT_ROOT";
+
+#[derive(Debug, Error)]
+pub enum ContextError {
+    #[error("Build context is larger than {0} bytes")]
+    TooLarge(u64),
+
+    #[error("Invalid build context archive: {0}")]
+    InvalidArchive(String),
+
+    #[error("Build context entry {0} points outside the context")]
+    UnsafePath(String),
+
+    #[error("Directory build contexts are disabled, set {CONTEXT_ROOT_VAR} to enable them")]
+    DirectoryDisabled,
+
+    #[error("{0} is not a directory inside {CONTEXT_ROOT_VAR}")]
+    InvalidDirectory(String),
+
+    #[error("Failed to read build context: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl From<zip::result::ZipError> for ContextError {
+    fn from(error: zip::result::ZipError) -> Self {
+        ContextError::InvalidArchive(error.to_string())
+    }
+}
+
+/// Where the project files of a build come from
+#[derive(Debug)]
+pub enum ContextSource {
+    /// An uploaded tar, gzip-compressed tar or zip archive
+    Archive(Vec<u8>),
+    /// A directory on the server, relative to `BUILD_CONTEXT_ROOT`
+    Directory(PathBuf),
+}
+
+/// A tar archive ready to be sent to the Docker daemon, with the Dockerfile it
+/// was built for
+#[derive(Debug)]
+pub struct BuildContext {
+    pub dockerfile: String,
+    pub archive: Vec<u8>,
+}
+
+impl BuildContext {
+    /// Copy the files of `source` that `.dockerignore` keeps and add
+    /// `dockerfile` as the context's `Dockerfile`, replacing any existing one
+    pub fn new(
+        dockerfile: &str,
+        source: Option<&ContextSource>,
+        max_size: u64,
+    ) -> Result<Self, ContextError> {
+        let mut writer = ContextWriter::new(max_size);
+        match source {
+            None => {}
+            Some(ContextSource::Archive(data)) => {
+                if data.starts_with(b"PK") {
+                    add_zip(&mut writer, data)?;
+                } else {
+                    add_tar(&mut writer, data)?;
+                }
+            }
+            Some(ContextSource::Directory(path)) => {
+                let root =
+                    std::env::var_os(CONTEXT_ROOT_VAR).ok_or(ContextError::DirectoryDisabled)?;
+                add_directory(&mut writer, Path::new(&root), path)?;
+            }
+        }
+
+        Ok(Self {
+            dockerfile: dockerfile.to_string(),
+            archive: writer.finish(dockerfile)?,
+        })
+    }
+}
+
+/// Patterns from a `.dockerignore` file. Like Docker, the last matching
+/// pattern decides, `!` re-includes, and a pattern that matches a directory
+/// also matches everything below it.
+#[derive(Debug, Default)]
+pub struct DockerIgnore {
+    patterns: Vec<(Regex, bool)>,
+}
+
+impl DockerIgnore {
+    pub fn parse(content: &str) -> Self {
+        let patterns = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (pattern, exception) = match line.strip_prefix('!') {
+                    Some(pattern) => (pattern.trim(), true),
+                    None => (line, false),
+                };
+                let pattern = clean_pattern(pattern);
+                if pattern.is_empty() {
+                    return None;
+                }
+                // Malformed character classes are skipped rather than matching everything
+                let regex = Regex::new(&format!("^{}(/.*)?$", pattern_regex(&pattern))).ok()?;
+                Some((regex, exception))
+            })
+            .collect();
+        Self { patterns }
+    }
+
+    pub fn is_ignored(&self, path: &str) -> bool {
+        self.patterns
+            .iter()
+            .rev()
+            .find(|(regex, _)| regex.is_match(path))
+            .is_some_and(|(_, exception)| !exception)
+    }
+
+    /// Whether nothing below the ignored directory `path` can be re-included
+    fn skips_directory(&self, path: &str) -> bool {
+        self.is_ignored(path) && !self.patterns.iter().any(|(_, exception)| *exception)
+    }
+}
+
+fn clean_pattern(pattern: &str) -> String {
+    pattern
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Translate a Go `filepath.Match` pattern, extended with `**`, into a regex
+fn pattern_regex(pattern: &str) -> String {
+    let mut regex = String::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // `**/` matches zero or more directories
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                }
+                if chars.peek().is_none() {
+                    regex.push_str(".*");
+                } else {
+                    regex.push_str("(.*/)?");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                regex.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    regex.push('^');
+                }
+                for c in chars.by_ref() {
+                    if c == '\\' || c == '[' {
+                        regex.push('\\');
+                    }
+                    regex.push(c);
+                    if c == ']' {
+                        break;
+                    }
+                }
+            }
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    regex.push_str(&regex::escape(&escaped.to_string()));
+                }
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex
+}
+
+/// Context-relative form of an entry path, `None` for the context root
+fn normalize(path: &str) -> Result<Option<String>, ContextError> {
+    let mut parts = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return Err(ContextError::UnsafePath(path.to_string())),
+            part => parts.push(part),
+        }
+    }
+    Ok((!parts.is_empty()).then(|| parts.join("/")))
+}
+
+/// Writes the entries that survive `.dockerignore` into the outgoing tar
+struct ContextWriter {
+    builder: tar::Builder<Vec<u8>>,
+    ignore: DockerIgnore,
+    max_size: u64,
+    remaining: u64,
+}
+
+impl ContextWriter {
+    fn new(max_size: u64) -> Self {
+        Self {
+            builder: tar::Builder::new(Vec::new()),
+            ignore: DockerIgnore::default(),
+            max_size,
+            remaining: max_size,
+        }
+    }
+
+    /// The normalized path of an entry to copy, `None` to leave it out
+    fn include(&self, path: &str) -> Result<Option<String>, ContextError> {
+        // The generated Dockerfile always replaces the uploaded one
+        Ok(normalize(path)?.filter(|p| p != "Dockerfile" && !self.ignore.is_ignored(p)))
+    }
+
+    fn file(&mut self, path: &str, mode: u32, reader: impl Read) -> Result<(), ContextError> {
+        let Some(path) = self.include(path)? else {
+            return Ok(());
+        };
+        // Declared sizes can't be trusted, so stop reading at the limit
+        let mut data = Vec::new();
+        reader.take(self.remaining + 1).read_to_end(&mut data)?;
+        if data.len() as u64 > self.remaining {
+            return Err(ContextError::TooLarge(self.max_size));
+        }
+        self.remaining -= data.len() as u64;
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_size(data.len() as u64);
+        header.set_mode(mode & 0o7777);
+        self.builder
+            .append_data(&mut header, path, data.as_slice())?;
+        Ok(())
+    }
+
+    /// Read the `.dockerignore` patterns, which count against the size
+    /// limit like any other entry
+    fn read_ignore(&mut self, reader: impl Read) -> Result<(), ContextError> {
+        let mut content = String::new();
+        reader
+            .take(self.remaining + 1)
+            .read_to_string(&mut content)?;
+        if content.len() as u64 > self.remaining {
+            return Err(ContextError::TooLarge(self.max_size));
+        }
+        self.ignore = DockerIgnore::parse(&content);
+        Ok(())
+    }
+
+    fn directory(&mut self, path: &str, mode: u32) -> Result<(), ContextError> {
+        let Some(path) = self.include(path)? else {
+            return Ok(());
+        };
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_size(0);
+        header.set_mode(mode & 0o7777);
+        self.builder
+            .append_data(&mut header, path, std::io::empty())?;
+        Ok(())
+    }
+
+    fn link(
+        &mut self,
+        path: &str,
+        target: &Path,
+        entry_type: tar::EntryType,
+    ) -> Result<(), ContextError> {
+        let Some(path) = self.include(path)? else {
+            return Ok(());
+        };
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(entry_type);
+        header.set_size(0);
+        header.set_mode(0o777);
+        self.builder.append_link(&mut header, path, target)?;
+        Ok(())
+    }
+
+    fn finish(mut self, dockerfile: &str) -> Result<Vec<u8>, ContextError> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(dockerfile.len() as u64);
+        header.set_mode(0o644);
+        self.builder
+            .append_data(&mut header, "Dockerfile", dockerfile.as_bytes())?;
+        Ok(self.builder.into_inner()?)
+    }
+}
+
+/// Reader over a tar archive, decompressing it when it is gzipped
+fn tar_reader(data: &[u8]) -> Box<dyn Read + '_> {
+    if data.starts_with(&[0x1f, 0x8b]) {
+        Box::new(flate2::read::GzDecoder::new(data))
+    } else {
+        Box::new(data)
+    }
+}
+
+fn add_tar(writer: &mut ContextWriter, data: &[u8]) -> Result<(), ContextError> {
+    let invalid = |e: std::io::Error| ContextError::InvalidArchive(e.to_string());
+
+    // `.dockerignore` can be anywhere in the archive, so look for it first
+    let mut archive = tar::Archive::new(tar_reader(data));
+    for entry in archive.entries().map_err(invalid)? {
+        let entry = entry.map_err(invalid)?;
+        let path = entry
+            .path()
+            .map_err(invalid)?
+            .to_string_lossy()
+            .into_owned();
+        if normalize(&path)?.as_deref() == Some(".dockerignore") {
+            writer.read_ignore(entry)?;
+        }
+    }
+
+    let mut archive = tar::Archive::new(tar_reader(data));
+    for entry in archive.entries().map_err(invalid)? {
+        let entry = entry.map_err(invalid)?;
+        let path = entry
+            .path()
+            .map_err(invalid)?
+            .to_string_lossy()
+            .into_owned();
+        let mode = entry.header().mode().unwrap_or(0o644);
+        let entry_type = entry.header().entry_type();
+        match entry_type {
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                writer.file(&path, mode, entry)?;
+            }
+            tar::EntryType::Directory => writer.directory(&path, mode)?,
+            tar::EntryType::Symlink | tar::EntryType::Link => {
+                if let Some(target) = entry.link_name().map_err(invalid)? {
+                    writer.link(&path, &target, entry_type)?;
+                }
+            }
+            // Devices, FIFOs and the like have no place in a build context
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn add_zip(writer: &mut ContextWriter, data: &[u8]) -> Result<(), ContextError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))?;
+    if let Ok(file) = archive.by_name(".dockerignore") {
+        writer.read_ignore(file)?;
+    }
+
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        let path = file
+            .enclosed_name()
+            .ok_or_else(|| ContextError::UnsafePath(file.name().to_string()))?
+            .to_string_lossy()
+            .into_owned();
+        if file.is_dir() {
+            writer.directory(&path, file.unix_mode().unwrap_or(0o755))?;
+        } else if file.is_symlink() {
+            let mut target = String::new();
+            file.read_to_string(&mut target)?;
+            writer.link(&path, Path::new(&target), tar::EntryType::Symlink)?;
+        } else {
+            let mode = file.unix_mode().unwrap_or(0o644);
+            writer.file(&path, mode, file)?;
+        }
+    }
+    Ok(())
+}
+
+fn add_directory(writer: &mut ContextWriter, root: &Path, path: &Path) -> Result<(), ContextError> {
+    let root = fs::canonicalize(root)?;
+    let invalid = || ContextError::InvalidDirectory(path.display().to_string());
+    // Resolving symlinks and `..` first keeps the directory inside the root
+    let directory = fs::canonicalize(root.join(path)).map_err(|_| invalid())?;
+    if !directory.starts_with(&root) || !directory.is_dir() {
+        return Err(invalid());
+    }
+
+    if let Ok(file) = fs::File::open(directory.join(".dockerignore")) {
+        writer.read_ignore(file)?;
+    }
+    add_directory_entries(writer, &directory, "")
+}
+
+fn add_directory_entries(
+    writer: &mut ContextWriter,
+    directory: &Path,
+    prefix: &str,
+) -> Result<(), ContextError> {
+    let mut entries = fs::read_dir(directory)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+        let metadata = entry.metadata()?;
+        let mode = metadata.permissions().mode();
+        if metadata.is_dir() {
+            if writer.ignore.skips_directory(&path) {
+                continue;
+            }
+            writer.directory(&path, mode)?;
+            add_directory_entries(writer, &entry.path(), &format!("{}/", path))?;
+        } else if metadata.is_symlink() {
+            writer.link(
+                &path,
+                &fs::read_link(entry.path())?,
+                tar::EntryType::Symlink,
+            )?;
+        } else if metadata.is_file() && writer.include(&path)?.is_some() {
+            writer.file(&path, mode, fs::File::open(entry.path())?)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn tar(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            builder
+                .append_data(&mut header, path, content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn entries(context: &BuildContext) -> Vec<(String, String)> {
+        let mut archive = tar::Archive::new(context.archive.as_slice());
+        archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let path = entry.path().unwrap().to_string_lossy().into_owned();
+                let mut content = String::new();
+                entry.read_to_string(&mut content).unwrap();
+                (path, content)
+            })
+            .collect()
+    }
+
+    fn paths(context: &BuildContext) -> Vec<String> {
+        entries(context).into_iter().map(|(path, _)| path).collect()
+    }
+
+    #[test]
+    fn test_dockerignore_patterns() {
+        let ignore = DockerIgnore::parse(
+            "# comment\n\
+             target\n\
+             **/*.log\n\
+             /docs/*.md\n\
+             !docs/README.md\n\
+             node_modules/\n\
+             .git\n\
+             file?.txt\n\
+             [abc].cfg\n",
+        );
+
+        assert!(ignore.is_ignored("target"));
+        assert!(ignore.is_ignored("target/debug/app"));
+        assert!(!ignore.is_ignored("src/target.rs"));
+        assert!(ignore.is_ignored("debug.log"));
+        assert!(ignore.is_ignored("logs/2024/debug.log"));
+        assert!(ignore.is_ignored("docs/guide.md"));
+        assert!(!ignore.is_ignored("docs/README.md"));
+        assert!(!ignore.is_ignored("docs/nested/guide.md"));
+        assert!(ignore.is_ignored("node_modules/left-pad/index.js"));
+        assert!(ignore.is_ignored(".git/HEAD"));
+        assert!(ignore.is_ignored("file1.txt"));
+        assert!(!ignore.is_ignored("file10.txt"));
+        assert!(ignore.is_ignored("a.cfg"));
+        assert!(!ignore.is_ignored("d.cfg"));
+        assert!(!ignore.is_ignored("# comment"));
+    }
+
+    #[test]
+    fn test_dockerfile_only() {
+        let context = BuildContext::new("FROM debian:bookworm", None, MAX_CONTEXT_SIZE).unwrap();
+        assert_eq!(
+            entries(&context),
+            [("Dockerfile".to_string(), "FROM debian:bookworm".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_tar_context() {
+        let archive = tar(&[
+            ("./.dockerignore", "*.log\nsecrets\n"),
+            ("./Dockerfile", "FROM scratch"),
+            ("./src/main.rs", "fn main() {}"),
+            ("./build.log", "noise"),
+            ("./secrets/token", "hunter2"),
+        ]);
+        let source = ContextSource::Archive(archive);
+        let context = BuildContext::new("FROM rust:1", Some(&source), MAX_CONTEXT_SIZE).unwrap();
+
+        assert_eq!(
+            entries(&context),
+            [
+                (".dockerignore".to_string(), "*.log\nsecrets\n".to_string()),
+                ("src/main.rs".to_string(), "fn main() {}".to_string()),
+                ("Dockerfile".to_string(), "FROM rust:1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gzipped_tar_context() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&tar(&[("app.py", "print()")])).unwrap();
+        let source = ContextSource::Archive(encoder.finish().unwrap());
+
+        let context = BuildContext::new("FROM python:3", Some(&source), MAX_CONTEXT_SIZE).unwrap();
+        assert_eq!(paths(&context), ["app.py", "Dockerfile"]);
+    }
+
+    #[test]
+    fn test_zip_context() {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file(".dockerignore", options).unwrap();
+        zip.write_all(b"dist\n").unwrap();
+        zip.add_directory("src/", options).unwrap();
+        zip.start_file("src/index.js", options).unwrap();
+        zip.write_all(b"console.log(1)").unwrap();
+        zip.start_file("dist/bundle.js", options).unwrap();
+        zip.write_all(b"minified").unwrap();
+        let source = ContextSource::Archive(zip.finish().unwrap().into_inner());
+
+        let context = BuildContext::new("FROM node:20", Some(&source), MAX_CONTEXT_SIZE).unwrap();
+        assert_eq!(
+            paths(&context),
+            [".dockerignore", "src", "src/index.js", "Dockerfile"]
+        );
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let source = ContextSource::Archive(tar(&[("a", "12345"), ("b", "67890")]));
+        let err = BuildContext::new("FROM scratch", Some(&source), 8).unwrap_err();
+        assert!(matches!(err, ContextError::TooLarge(_)));
+
+        // Ignored files don't count
+        let source = ContextSource::Archive(tar(&[(".dockerignore", "a"), ("a", "12345")]));
+        assert!(BuildContext::new("FROM scratch", Some(&source), 1024).is_ok());
+
+        // `.dockerignore` is read before anything else, even when it ignores itself
+        let source = ContextSource::Archive(tar(&[(".dockerignore", ".dockerignore\n*.log\n")]));
+        let err = BuildContext::new("FROM scratch", Some(&source), 8).unwrap_err();
+        assert!(matches!(err, ContextError::TooLarge(_)));
+    }
+
+    #[test]
+    fn test_directory_context() {
+        let root = std::env::temp_dir().join(format!("build-context-{}", std::process::id()));
+        let project = root.join("project");
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::create_dir_all(project.join("node_modules/left-pad")).unwrap();
+        fs::write(project.join(".dockerignore"), "node_modules\n").unwrap();
+        fs::write(project.join("src/index.js"), "console.log(1)").unwrap();
+        fs::write(project.join("node_modules/left-pad/index.js"), "").unwrap();
+        std::os::unix::fs::symlink("src/index.js", project.join("main.js")).unwrap();
+
+        let mut writer = ContextWriter::new(MAX_CONTEXT_SIZE);
+        add_directory(&mut writer, &root, Path::new("project")).unwrap();
+        let context = BuildContext {
+            dockerfile: String::new(),
+            archive: writer.finish("FROM node:20").unwrap(),
+        };
+        assert_eq!(
+            paths(&context),
+            [
+                ".dockerignore",
+                "main.js",
+                "src",
+                "src/index.js",
+                "Dockerfile"
+            ]
+        );
+
+        // Paths may not climb out of the root
+        let mut writer = ContextWriter::new(MAX_CONTEXT_SIZE);
+        let err = add_directory(&mut writer, &project, Path::new("..")).unwrap_err();
+        assert!(matches!(err, ContextError::InvalidDirectory(_)));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_unsafe_paths_are_rejected() {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(1);
+        header.set_mode(0o644);
+        // `append_data` refuses `..`, so write the name into the header directly
+        header.as_gnu_mut().unwrap().name[..9].copy_from_slice(b"../escape");
+        header.set_cksum();
+        let mut builder = tar::Builder::new(Vec::new());
+        builder.append(&header, &b"x"[..]).unwrap();
+        let source = ContextSource::Archive(builder.into_inner().unwrap());
+
+        let err = BuildContext::new("FROM scratch", Some(&source), MAX_CONTEXT_SIZE).unwrap_err();
+        assert!(matches!(err, ContextError::UnsafePath(_)));
+        assert!(matches!(
+            BuildContext::new(
+                "FROM scratch",
+                Some(&ContextSource::Archive(b"not an archive".to_vec())),
+                MAX_CONTEXT_SIZE,
+            ),
+            Err(ContextError::InvalidArchive(_))
+        ));
+    }
+}
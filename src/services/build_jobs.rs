@@ -0,0 +1,384 @@
+//! Image builds that run in the background, independently of the request
+//! that started them.
+
+use crate::models::build::{BuildEvent, BuildJob, BuildOptions, BuildStatus};
+use crate::services::build_context::BuildContext;
+use crate::services::docker_service::DockerService;
+use futures_util::stream::StreamExt;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use tokio::task::AbortHandle;
+
+/// Finished jobs kept for polling; older ones are dropped on the next submit
+const MAX_FINISHED_JOBS: usize = 100;
+
+/// Log lines kept per job; older lines are dropped as new ones arrive
+const MAX_LOG_LINES: usize = 1000;
+
+#[derive(Debug, Error)]
+pub enum BuildJobError {
+    #[error("Build {0} not found")]
+    NotFound(String),
+
+    #[error("Build {0} has already finished")]
+    Finished(String),
+
+    #[error("{0} builds are already queued, try again later")]
+    QueueFull(usize),
+}
+
+struct Entry {
+    seq: u64,
+    job: BuildJob,
+    task: Option<AbortHandle>,
+}
+
+/// Registry of build jobs. At most `max_concurrent` builds talk to the
+/// Docker daemon at once and at most `max_queued` wait for their turn,
+/// each holding its build context in memory.
+pub struct BuildJobs {
+    docker: Arc<DockerService>,
+    permits: Arc<Semaphore>,
+    max_queued: usize,
+    jobs: Arc<Mutex<HashMap<String, Entry>>>,
+    next_id: AtomicU64,
+}
+
+impl BuildJobs {
+    pub fn new(docker: Arc<DockerService>, max_concurrent: usize, max_queued: usize) -> Self {
+        Self {
+            docker,
+            permits: Arc::new(Semaphore::new(max_concurrent)),
+            max_queued,
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Queue a build and return its job ID straight away
+    pub fn submit(
+        &self,
+        context: BuildContext,
+        tag: String,
+        options: BuildOptions,
+    ) -> Result<String, BuildJobError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let queued = jobs
+            .values()
+            .filter(|entry| entry.job.status == BuildStatus::Queued)
+            .count();
+        if queued >= self.max_queued {
+            return Err(BuildJobError::QueueFull(queued));
+        }
+        evict_finished(&mut jobs, MAX_FINISHED_JOBS - 1);
+
+        let seq = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let id = format!("build-{}", seq);
+        let job = BuildJob {
+            id: id.clone(),
+            tag: tag.clone(),
+            status: BuildStatus::Queued,
+            logs: VecDeque::new(),
+            image_id: None,
+            error: None,
+            created: now(),
+            started: None,
+            finished: None,
+        };
+
+        // Register before spawning so the task always finds its entry
+        jobs.insert(
+            id.clone(),
+            Entry {
+                seq,
+                job,
+                task: None,
+            },
+        );
+
+        let task = tokio::spawn(run(
+            id.clone(),
+            context,
+            tag,
+            options,
+            self.docker.clone(),
+            self.permits.clone(),
+            self.jobs.clone(),
+        ));
+        if let Some(entry) = jobs.get_mut(&id) {
+            entry.task = Some(task.abort_handle());
+        }
+
+        Ok(id)
+    }
+
+    pub fn get(&self, id: &str) -> Option<BuildJob> {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.get(id).map(|entry| entry.job.clone())
+    }
+
+    /// All jobs, oldest first
+    pub fn list(&self) -> Vec<BuildJob> {
+        let jobs = self.jobs.lock().unwrap();
+        let mut entries: Vec<&Entry> = jobs.values().collect();
+        entries.sort_by_key(|entry| entry.seq);
+        entries.into_iter().map(|entry| entry.job.clone()).collect()
+    }
+
+    /// Stop a queued or running build. Aborting the task drops its event
+    /// stream, which closes the connection to the Docker daemon.
+    pub fn cancel(&self, id: &str) -> Result<BuildJob, BuildJobError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let entry = jobs
+            .get_mut(id)
+            .ok_or_else(|| BuildJobError::NotFound(id.to_string()))?;
+        if entry.job.status.is_finished() {
+            return Err(BuildJobError::Finished(id.to_string()));
+        }
+
+        if let Some(task) = entry.task.take() {
+            task.abort();
+        }
+        entry.job.status = BuildStatus::Cancelled;
+        entry.job.finished = Some(now());
+        Ok(entry.job.clone())
+    }
+}
+
+async fn run(
+    id: String,
+    context: BuildContext,
+    tag: String,
+    options: BuildOptions,
+    docker: Arc<DockerService>,
+    permits: Arc<Semaphore>,
+    jobs: Arc<Mutex<HashMap<String, Entry>>>,
+) {
+    // Held until the build ends, so queued builds start in submission order
+    let Ok(_permit) = permits.acquire_owned().await else {
+        return;
+    };
+
+    let update = |apply: &dyn Fn(&mut BuildJob)| {
+        let mut jobs = jobs.lock().unwrap();
+        // A cancelled job keeps the state it was cancelled in
+        if let Some(entry) = jobs.get_mut(&id) {
+            if !entry.job.status.is_finished() {
+                apply(&mut entry.job);
+            }
+        }
+    };
+
+    update(&|job| {
+        job.status = BuildStatus::Running;
+        job.started = Some(now());
+    });
+    tracing::info!("Build {} started for tag {}", id, tag);
+
+    let mut events = std::pin::pin!(docker.build_image_stream(context, &tag, options));
+
+    while let Some(event) = events.next().await {
+        match event {
+            BuildEvent::Error { message } => {
+                tracing::error!("Build {} failed: {}", id, message);
+                update(&|job| finish(job, Some(message.clone())));
+                return;
+            }
+            BuildEvent::ImageId { id: image_id } => {
+                update(&|job| job.image_id = Some(image_id.clone()));
+            }
+            event => {
+                if let Some(line) = event.log_line() {
+                    update(&|job| push_log(job, line.clone()));
+                }
+            }
+        }
+    }
+
+    tracing::info!("Build {} finished", id);
+    update(&|job| finish(job, None));
+}
+
+fn push_log(job: &mut BuildJob, line: String) {
+    if job.logs.len() >= MAX_LOG_LINES {
+        job.logs.pop_front();
+    }
+    job.logs.push_back(line);
+}
+
+/// Drop the oldest finished jobs until at most `keep` are left
+fn evict_finished(jobs: &mut HashMap<String, Entry>, keep: usize) {
+    let mut finished: Vec<(u64, String)> = jobs
+        .iter()
+        .filter(|(_, entry)| entry.job.status.is_finished())
+        .map(|(id, entry)| (entry.seq, id.clone()))
+        .collect();
+    if finished.len() <= keep {
+        return;
+    }
+
+    finished.sort_unstable();
+    for (_, id) in &finished[..finished.len() - keep] {
+        jobs.remove(id);
+    }
+}
+
+fn finish(job: &mut BuildJob, error: Option<String>) {
+    job.status = if error.is_some() {
+        BuildStatus::Failed
+    } else {
+        BuildStatus::Succeeded
+    };
+    job.error = error;
+    job.finished = Some(now());
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::build_context::MAX_CONTEXT_SIZE;
+
+    fn jobs(max_concurrent: usize, max_queued: usize) -> BuildJobs {
+        // Connections are made lazily, and these builds never get to start one
+        let client = bollard::Docker::connect_with_http(
+            "http://127.0.0.1:9",
+            1,
+            bollard::API_DEFAULT_VERSION,
+        )
+        .unwrap();
+        let docker = Arc::new(DockerService::with_client(client));
+        BuildJobs::new(docker, max_concurrent, max_queued)
+    }
+
+    fn context() -> BuildContext {
+        BuildContext::new("FROM debian:bookworm", None, MAX_CONTEXT_SIZE).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_builds_wait_for_a_permit() {
+        let jobs = jobs(0, 4);
+        let id = jobs
+            .submit(context(), "test".to_string(), BuildOptions::default())
+            .unwrap();
+        tokio::task::yield_now().await;
+
+        let job = jobs.get(&id).unwrap();
+        assert_eq!(job.status, BuildStatus::Queued);
+        assert_eq!(job.started, None);
+        assert!(jobs.get("build-0").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancel() {
+        let jobs = jobs(0, 4);
+        let first = jobs
+            .submit(context(), "a".to_string(), BuildOptions::default())
+            .unwrap();
+        let second = jobs
+            .submit(context(), "b".to_string(), BuildOptions::default())
+            .unwrap();
+
+        let cancelled = jobs.cancel(&first).unwrap();
+        assert_eq!(cancelled.status, BuildStatus::Cancelled);
+        assert!(cancelled.finished.is_some());
+        assert!(matches!(
+            jobs.cancel(&first),
+            Err(BuildJobError::Finished(_))
+        ));
+        assert!(matches!(
+            jobs.cancel("build-0"),
+            Err(BuildJobError::NotFound(_))
+        ));
+
+        let statuses: Vec<BuildStatus> = jobs.list().iter().map(|job| job.status).collect();
+        assert_eq!(statuses, [BuildStatus::Cancelled, BuildStatus::Queued]);
+        assert_eq!(jobs.get(&second).unwrap().tag, "b");
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_rejects_builds() {
+        let jobs = jobs(0, 2);
+        let first = jobs
+            .submit(context(), "a".to_string(), BuildOptions::default())
+            .unwrap();
+        jobs.submit(context(), "b".to_string(), BuildOptions::default())
+            .unwrap();
+        assert!(matches!(
+            jobs.submit(context(), "c".to_string(), BuildOptions::default()),
+            Err(BuildJobError::QueueFull(2))
+        ));
+
+        // Cancelled builds no longer count against the queue
+        jobs.cancel(&first).unwrap();
+        assert!(jobs
+            .submit(context(), "c".to_string(), BuildOptions::default())
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_oldest_finished_jobs_are_evicted() {
+        let jobs = jobs(0, MAX_FINISHED_JOBS + 1);
+        let ids: Vec<String> = (0..MAX_FINISHED_JOBS + 1)
+            .map(|_| {
+                let id = jobs
+                    .submit(context(), "test".to_string(), BuildOptions::default())
+                    .unwrap();
+                jobs.cancel(&id).unwrap();
+                id
+            })
+            .collect();
+        assert!(jobs.get(&ids[0]).is_none());
+        assert!(jobs.get(&ids[1]).is_some());
+        assert_eq!(jobs.list().len(), MAX_FINISHED_JOBS);
+    }
+
+    #[test]
+    fn test_finish() {
+        let mut job = BuildJob {
+            id: "build-1".to_string(),
+            tag: "test".to_string(),
+            status: BuildStatus::Running,
+            logs: VecDeque::new(),
+            image_id: None,
+            error: None,
+            created: 0,
+            started: Some(0),
+            finished: None,
+        };
+        finish(&mut job, Some("exit code 1".to_string()));
+        assert_eq!(job.status, BuildStatus::Failed);
+        assert_eq!(job.error.as_deref(), Some("exit code 1"));
+        assert!(job.finished.is_some());
+    }
+
+    #[test]
+    fn test_push_log_keeps_latest_lines() {
+        let mut job = BuildJob {
+            id: "build-1".to_string(),
+            tag: "test".to_string(),
+            status: BuildStatus::Running,
+            logs: VecDeque::new(),
+            image_id: None,
+            error: None,
+            created: 0,
+            started: Some(0),
+            finished: None,
+        };
+        for i in 0..MAX_LOG_LINES + 5 {
+            push_log(&mut job, format!("line {}", i));
+        }
+        assert_eq!(job.logs.len(), MAX_LOG_LINES);
+        assert_eq!(job.logs[0], "line 5");
+        assert_eq!(
+            job.logs[MAX_LOG_LINES - 1],
+            format!("line {}", MAX_LOG_LINES + 4)
+        );
+    }
+}
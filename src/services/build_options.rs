@@ -0,0 +1,245 @@
+//! Validation of user-supplied build options before they reach the Docker API.
+
+use crate::models::build::{BuildOptions, PullPolicy};
+use crate::models::dockerfile::{is_label_key, is_plain_word, is_variable_name};
+use crate::services::dockerfile_parser::{from_stage, parse, syntax_directive};
+use regex::Regex;
+use std::sync::OnceLock;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum BuildOptionError {
+    #[error("Invalid image tag: {0}")]
+    InvalidTag(String),
+
+    #[error("Invalid build argument name: {0}")]
+    InvalidBuildArg(String),
+
+    #[error("Invalid label key: {0}")]
+    InvalidLabel(String),
+
+    #[error("Build argument and label values cannot contain control characters")]
+    ControlCharacters,
+
+    #[error("Invalid platform: {0}")]
+    InvalidPlatform(String),
+
+    #[error("Target stage {0} is not defined in the Dockerfile")]
+    UnknownTarget(String),
+
+    #[error("Pull policy never cannot be used with the BuildKit frontend {0}, which is always fetched from its registry")]
+    RemoteFrontend(String),
+}
+
+/// Check `options` and the main `tag` against the Dockerfile they build
+pub fn validate(
+    options: &BuildOptions,
+    tag: &str,
+    dockerfile: &str,
+) -> Result<(), BuildOptionError> {
+    if let Some(tag) = std::iter::once(tag)
+        .chain(options.extra_tags.iter().map(String::as_str))
+        .find(|t| !is_image_reference(t))
+    {
+        return Err(BuildOptionError::InvalidTag(tag.to_string()));
+    }
+
+    if let Some(name) = options.build_args.keys().find(|n| !is_variable_name(n)) {
+        return Err(BuildOptionError::InvalidBuildArg(name.clone()));
+    }
+    if let Some(key) = options.labels.keys().find(|k| !is_label_key(k)) {
+        return Err(BuildOptionError::InvalidLabel(key.clone()));
+    }
+    let mut values = options.build_args.values().chain(options.labels.values());
+    if values.any(|v| v.chars().any(char::is_control)) {
+        return Err(BuildOptionError::ControlCharacters);
+    }
+
+    if let Some(platform) = &options.platform {
+        if !is_platform(platform) {
+            return Err(BuildOptionError::InvalidPlatform(platform.clone()));
+        }
+    }
+
+    // BuildKit resolves custom frontends against the registry, not local images
+    if options.pull == PullPolicy::Never {
+        let frontend = options
+            .build_args
+            .get("BUILDKIT_SYNTAX")
+            .map(String::as_str)
+            .or_else(|| syntax_directive(dockerfile));
+        if let Some(frontend) = frontend {
+            return Err(BuildOptionError::RemoteFrontend(frontend.to_string()));
+        }
+    }
+
+    if let Some(target) = &options.target {
+        // An unparsable Dockerfile is left for the daemon to report
+        let stages: Vec<String> = parse(dockerfile)
+            .unwrap_or_default()
+            .iter()
+            .filter(|i| i.keyword == "FROM")
+            .filter_map(|i| from_stage(&i.arguments).1.map(str::to_ascii_lowercase))
+            .collect();
+        if !is_plain_word(target) || !stages.contains(&target.to_ascii_lowercase()) {
+            return Err(BuildOptionError::UnknownTarget(target.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+/// `[registry[:port]/]name[:tag]` as accepted by `docker tag`
+pub fn is_image_reference(reference: &str) -> bool {
+    static REFERENCE: OnceLock<Regex> = OnceLock::new();
+    let reference_regex = REFERENCE.get_or_init(|| {
+        let component = r"[a-z0-9]+(?:(?:[._]|__|-+)[a-z0-9]+)*";
+        Regex::new(&format!(
+            r"^(?:[a-zA-Z0-9-]+(?:\.[a-zA-Z0-9-]+)*(?::[0-9]+)?/)?{c}(?:/{c})*(?::[A-Za-z0-9_][A-Za-z0-9_.-]{{0,127}})?$",
+            c = component
+        ))
+        .unwrap()
+    });
+    reference.len() <= 255 && reference_regex.is_match(reference)
+}
+
+/// `os[/arch[/variant]]`, e.g. `linux/amd64` or `linux/arm64/v8`
+fn is_platform(platform: &str) -> bool {
+    let parts: Vec<&str> = platform.split('/').collect();
+    parts.len() <= 3
+        && parts.iter().all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    const DOCKERFILE: &str =
+        "FROM rust:1 AS build\nRUN cargo build\nFROM debian:bookworm AS Runtime\n";
+
+    #[test]
+    fn test_valid_options() {
+        let options = BuildOptions {
+            no_cache: true,
+            pull: PullPolicy::Never,
+            build_args: BTreeMap::from([("RUST_VERSION".to_string(), "1.83".to_string())]),
+            target: Some("runtime".to_string()),
+            platform: Some("linux/arm64/v8".to_string()),
+            labels: BTreeMap::from([(
+                "org.opencontainers.image.source".to_string(),
+                "https://example.com/app".to_string(),
+            )]),
+            extra_tags: vec![
+                "registry.example.com:5000/team/app:1.2.3".to_string(),
+                "app".to_string(),
+            ],
+        };
+        validate(&options, "containerhelper/app:latest", DOCKERFILE).unwrap();
+    }
+
+    #[test]
+    fn test_image_references() {
+        for reference in [
+            "app",
+            "app:v1",
+            "team/app",
+            "localhost:5000/app:1.0",
+            "a__b/c-d.e",
+        ] {
+            assert!(is_image_reference(reference), "{}", reference);
+        }
+        for reference in [
+            "",
+            "App",
+            "app:",
+            "app:-x",
+            "app tag",
+            "app:v1\nRUN id",
+            "../app",
+            "app@sha256",
+        ] {
+            assert!(!is_image_reference(reference), "{:?}", reference);
+        }
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let check = |options: BuildOptions| validate(&options, "app", DOCKERFILE).unwrap_err();
+
+        assert!(matches!(
+            validate(&BuildOptions::default(), "My App", DOCKERFILE),
+            Err(BuildOptionError::InvalidTag(_))
+        ));
+        assert!(matches!(
+            check(BuildOptions {
+                extra_tags: vec!["app:bad tag".to_string()],
+                ..Default::default()
+            }),
+            BuildOptionError::InvalidTag(_)
+        ));
+        assert!(matches!(
+            check(BuildOptions {
+                build_args: BTreeMap::from([("1ARG".to_string(), String::new())]),
+                ..Default::default()
+            }),
+            BuildOptionError::InvalidBuildArg(_)
+        ));
+        assert!(matches!(
+            check(BuildOptions {
+                labels: BTreeMap::from([("maintainer".to_string(), "a\nb".to_string())]),
+                ..Default::default()
+            }),
+            BuildOptionError::ControlCharacters
+        ));
+        assert!(matches!(
+            check(BuildOptions {
+                platform: Some("linux/amd64/v2/extra".to_string()),
+                ..Default::default()
+            }),
+            BuildOptionError::InvalidPlatform(_)
+        ));
+        assert!(matches!(
+            check(BuildOptions {
+                target: Some("test".to_string()),
+                ..Default::default()
+            }),
+            BuildOptionError::UnknownTarget(_)
+        ));
+    }
+
+    #[test]
+    fn test_offline_builds_reject_remote_frontends() {
+        let never = BuildOptions {
+            pull: PullPolicy::Never,
+            ..Default::default()
+        };
+        let dockerfile = format!("# syntax=docker/dockerfile:1\n\n{}", DOCKERFILE);
+        assert!(matches!(
+            validate(&never, "app", &dockerfile),
+            Err(BuildOptionError::RemoteFrontend(frontend)) if frontend == "docker/dockerfile:1"
+        ));
+
+        let never_with_arg = BuildOptions {
+            build_args: BTreeMap::from([(
+                "BUILDKIT_SYNTAX".to_string(),
+                "docker/dockerfile:1.7".to_string(),
+            )]),
+            ..never.clone()
+        };
+        assert!(matches!(
+            validate(&never_with_arg, "app", DOCKERFILE),
+            Err(BuildOptionError::RemoteFrontend(_))
+        ));
+
+        // Other pull policies may fetch it, and a later comment is no directive
+        validate(&BuildOptions::default(), "app", &dockerfile).unwrap();
+        let comment = format!("{}# syntax=docker/dockerfile:1\n", DOCKERFILE);
+        validate(&never, "app", &comment).unwrap();
+    }
+}
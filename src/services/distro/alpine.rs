@@ -0,0 +1,116 @@
+//! Alpine release metadata.
+//!
+//! Alpine pins one minor version of each runtime per release, so the generator
+//! needs to know what a release ships to install (or reject) a requested version.
+
+pub struct AlpineRelease {
+    pub version: &'static str,
+    /// Minor version of the `python3` package
+    pub python: &'static str,
+    /// Major version of the `nodejs` package
+    pub nodejs: &'static str,
+    /// Major version of the `nodejs-current` package
+    pub nodejs_current: &'static str,
+    /// Minor version of the `ruby` package
+    pub ruby: &'static str,
+    /// Minor version of the `perl` package
+    pub perl: &'static str,
+    /// Minor versions with `phpXY` packages
+    pub php: &'static [&'static str],
+    /// Major versions with `openjdkN-jdk` packages
+    pub openjdk: &'static [&'static str],
+}
+
+/// Supported releases, oldest first
+pub const RELEASES: &[AlpineRelease] = &[
+    AlpineRelease {
+        version: "3.17",
+        python: "3.10",
+        nodejs: "18",
+        nodejs_current: "19",
+        ruby: "3.1",
+        perl: "5.36",
+        php: &["8.1"],
+        openjdk: &["8", "11", "17"],
+    },
+    AlpineRelease {
+        version: "3.18",
+        python: "3.11",
+        nodejs: "18",
+        nodejs_current: "20",
+        ruby: "3.2",
+        perl: "5.36",
+        php: &["8.1", "8.2"],
+        openjdk: &["8", "11", "17"],
+    },
+    AlpineRelease {
+        version: "3.19",
+        python: "3.11",
+        nodejs: "20",
+        nodejs_current: "21",
+        ruby: "3.2",
+        perl: "5.38",
+        php: &["8.1", "8.2", "8.3"],
+        openjdk: &["8", "11", "17", "21"],
+    },
+    AlpineRelease {
+        version: "3.20",
+        python: "3.12",
+        nodejs: "20",
+        nodejs_current: "22",
+        ruby: "3.3",
+        perl: "5.38",
+        php: &["8.2", "8.3"],
+        openjdk: &["8", "11", "17", "21"],
+    },
+    AlpineRelease {
+        version: "3.21",
+        python: "3.12",
+        nodejs: "22",
+        nodejs_current: "23",
+        ruby: "3.3",
+        perl: "5.40",
+        php: &["8.3", "8.4"],
+        openjdk: &["8", "11", "17", "21"],
+    },
+    AlpineRelease {
+        version: "3.22",
+        python: "3.12",
+        nodejs: "22",
+        nodejs_current: "24",
+        ruby: "3.4",
+        perl: "5.40",
+        php: &["8.3", "8.4"],
+        openjdk: &["8", "11", "17", "21"],
+    },
+];
+
+/// Look up a release by `3.20`, `3.20.3` or `latest` (the newest known release)
+pub fn release(version: &str) -> Option<&'static AlpineRelease> {
+    if version == "latest" {
+        return RELEASES.last();
+    }
+
+    let mut parts = version.split('.');
+    let minor_version = match (parts.next(), parts.next()) {
+        (Some(major), Some(minor)) => format!("{}.{}", major, minor),
+        _ => return None,
+    };
+
+    RELEASES.iter().find(|r| r.version == minor_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_lookup() {
+        assert_eq!(release("3.19").unwrap().python, "3.11");
+        assert_eq!(release("3.19").unwrap().php, &["8.1", "8.2", "8.3"]);
+        assert_eq!(release("3.20.3").unwrap().version, "3.20");
+        assert_eq!(release("latest").unwrap().version, "3.22");
+        assert!(release("3.5").is_none());
+        assert!(release("edge").is_none());
+    }
+}
@@ -0,0 +1,66 @@
+//! Debian release metadata.
+
+pub struct DebianRelease {
+    pub codename: &'static str,
+    pub number: &'static str,
+    /// Minor version of the `python3` package
+    pub python: &'static str,
+    /// Minor version of the `ruby` package
+    pub ruby: &'static str,
+    /// Minor version of the `perl` package
+    pub perl: &'static str,
+    /// Major versions with `openjdk-N-jdk-headless` packages
+    pub openjdk: &'static [&'static str],
+}
+
+/// Supported releases, oldest first
+pub const RELEASES: &[DebianRelease] = &[
+    DebianRelease {
+        codename: "bullseye",
+        number: "11",
+        python: "3.9",
+        ruby: "2.7",
+        perl: "5.32",
+        openjdk: &["11", "17"],
+    },
+    DebianRelease {
+        codename: "bookworm",
+        number: "12",
+        python: "3.11",
+        ruby: "3.1",
+        perl: "5.36",
+        openjdk: &["17"],
+    },
+    DebianRelease {
+        codename: "trixie",
+        number: "13",
+        python: "3.13",
+        ruby: "3.3",
+        perl: "5.40",
+        openjdk: &["21"],
+    },
+];
+
+/// Look up a release by codename (`bookworm`, `bookworm-slim`) or number (`12`, `12.5`)
+pub fn release(version: &str) -> Option<&'static DebianRelease> {
+    let version = version.strip_suffix("-slim").unwrap_or(version);
+    let number = version.split('.').next().unwrap_or(version);
+
+    RELEASES
+        .iter()
+        .find(|r| r.codename == version || r.number == number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_lookup() {
+        assert_eq!(release("bookworm").unwrap().python, "3.11");
+        assert_eq!(release("bookworm-slim").unwrap().number, "12");
+        assert_eq!(release("12.5").unwrap().codename, "bookworm");
+        assert_eq!(release("11").unwrap().python, "3.9");
+        assert!(release("buster").is_none());
+    }
+}
@@ -0,0 +1,271 @@
+//! Base operating systems supported by the Dockerfile generator.
+
+pub mod alpine;
+pub mod debian;
+pub mod ubuntu;
+
+use crate::models::config::OsConfig;
+use crate::models::dockerfile::is_plain_word;
+use crate::services::dockerfile_generator::GeneratorError;
+use crate::services::package_manager::PackageManager;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distro {
+    Ubuntu,
+    Debian,
+    Alpine,
+    Fedora,
+    Rocky,
+    Arch,
+    OpenSuse,
+}
+
+impl Distro {
+    pub fn from_os_type(os_type: &str) -> Option<Self> {
+        match os_type {
+            "ubuntu" => Some(Distro::Ubuntu),
+            "debian" => Some(Distro::Debian),
+            "alpine" => Some(Distro::Alpine),
+            "fedora" => Some(Distro::Fedora),
+            "rocky" | "rockylinux" => Some(Distro::Rocky),
+            "arch" | "archlinux" => Some(Distro::Arch),
+            "opensuse" | "opensuse-leap" => Some(Distro::OpenSuse),
+            _ => None,
+        }
+    }
+
+    pub fn package_manager(self) -> PackageManager {
+        match self {
+            Distro::Ubuntu | Distro::Debian => PackageManager::Apt,
+            Distro::Alpine => PackageManager::Apk,
+            Distro::Fedora | Distro::Rocky => PackageManager::Dnf,
+            Distro::OpenSuse => PackageManager::Zypper,
+            Distro::Arch => PackageManager::Pacman,
+        }
+    }
+}
+
+/// Runtimes whose distro package versions are tracked per release
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Runtime {
+    Python,
+    Ruby,
+    Perl,
+}
+
+/// The resolved base OS a Dockerfile is generated for
+#[derive(Debug, Clone)]
+pub struct TargetOs {
+    pub distro: Distro,
+    pub version: String,
+}
+
+impl TargetOs {
+    pub fn from_config(os: &OsConfig) -> Result<Self, GeneratorError> {
+        let distro = Distro::from_os_type(&os.os_type)
+            .ok_or_else(|| GeneratorError::UnsupportedOs(os.os_type.clone()))?;
+
+        // The version ends up in the image reference and in shell commands
+        let unknown_alpine = distro == Distro::Alpine && alpine::release(&os.version).is_none();
+        if unknown_alpine || !is_plain_word(&os.version) || os.version.contains(['@', '+']) {
+            return Err(GeneratorError::UnsupportedOsVersion {
+                os: os.os_type.clone(),
+                version: os.version.clone(),
+            });
+        }
+
+        Ok(Self {
+            distro,
+            version: os.version.clone(),
+        })
+    }
+
+    pub fn package_manager(&self) -> PackageManager {
+        self.distro.package_manager()
+    }
+
+    /// Release metadata when the target is Alpine
+    pub fn alpine_release(&self) -> Option<&'static alpine::AlpineRelease> {
+        match self.distro {
+            Distro::Alpine => alpine::release(&self.version),
+            _ => None,
+        }
+    }
+
+    /// Minor version of a runtime shipped by the target's own repositories,
+    /// when the release is one we have metadata for
+    pub fn packaged_version(&self, runtime: Runtime) -> Option<&'static str> {
+        match self.distro {
+            Distro::Alpine => self.alpine_release().map(|r| match runtime {
+                Runtime::Python => r.python,
+                Runtime::Ruby => r.ruby,
+                Runtime::Perl => r.perl,
+            }),
+            Distro::Debian => debian::release(&self.version).map(|r| match runtime {
+                Runtime::Python => r.python,
+                Runtime::Ruby => r.ruby,
+                Runtime::Perl => r.perl,
+            }),
+            Distro::Ubuntu => ubuntu::release(&self.version).map(|r| match runtime {
+                Runtime::Python => r.python,
+                Runtime::Ruby => r.ruby,
+                Runtime::Perl => r.perl,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Major versions of the distribution's OpenJDK packages, when the
+    /// release is one we have metadata for
+    pub fn openjdk_versions(&self) -> Option<&'static [&'static str]> {
+        match self.distro {
+            Distro::Alpine => self.alpine_release().map(|r| r.openjdk),
+            Distro::Debian => debian::release(&self.version).map(|r| r.openjdk),
+            Distro::Ubuntu => ubuntu::release(&self.version).map(|r| r.openjdk),
+            _ => None,
+        }
+    }
+
+    /// Human readable name used in error messages, e.g. `alpine 3.20`
+    pub fn describe(&self) -> String {
+        match self.alpine_release() {
+            Some(release) if self.version == "latest" => format!("alpine {}", release.version),
+            _ => format!("{:?} {}", self.distro, self.version).to_lowercase(),
+        }
+    }
+
+    /// Base image reference for the FROM instruction
+    pub fn image(&self) -> String {
+        match self.distro {
+            Distro::Ubuntu => format!("ubuntu:{}", self.version),
+            Distro::Debian => format!("debian:{}", self.version),
+            // "latest" is pinned to the newest release the package mapping knows about
+            Distro::Alpine if self.version == "latest" => {
+                format!(
+                    "alpine:{}",
+                    alpine::RELEASES[alpine::RELEASES.len() - 1].version
+                )
+            }
+            Distro::Alpine => format!("alpine:{}", self.version),
+            Distro::Fedora => format!("fedora:{}", self.version),
+            Distro::Rocky => format!("rockylinux/rockylinux:{}", self.version),
+            // `base` is the tag `latest` points at
+            Distro::Arch if self.version == "latest" => "archlinux:base".to_string(),
+            Distro::Arch => format!("archlinux:{}", self.version),
+            Distro::OpenSuse if self.version == "tumbleweed" => {
+                "opensuse/tumbleweed:latest".to_string()
+            }
+            Distro::OpenSuse => format!("opensuse/leap:{}", self.version),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(os_type: &str, version: &str) -> TargetOs {
+        TargetOs::from_config(&OsConfig {
+            os_type: os_type.to_string(),
+            version: version.to_string(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_images() {
+        assert_eq!(target("ubuntu", "22.04").image(), "ubuntu:22.04");
+        assert_eq!(target("alpine", "3.19").image(), "alpine:3.19");
+        assert_eq!(target("alpine", "3.20.3").image(), "alpine:3.20.3");
+        assert_eq!(target("alpine", "latest").image(), "alpine:3.22");
+        assert_eq!(target("fedora", "40").image(), "fedora:40");
+        assert_eq!(target("rocky", "9").image(), "rockylinux/rockylinux:9");
+        assert_eq!(target("arch", "latest").image(), "archlinux:base");
+        assert_eq!(target("opensuse", "15.6").image(), "opensuse/leap:15.6");
+        assert_eq!(
+            target("opensuse", "tumbleweed").image(),
+            "opensuse/tumbleweed:latest"
+        );
+    }
+
+    #[test]
+    fn test_package_managers() {
+        assert_eq!(
+            target("debian", "bookworm").package_manager(),
+            PackageManager::Apt
+        );
+        assert_eq!(
+            target("alpine", "latest").package_manager(),
+            PackageManager::Apk
+        );
+        assert_eq!(
+            target("rockylinux", "9").package_manager(),
+            PackageManager::Dnf
+        );
+        assert_eq!(
+            target("opensuse", "15.6").package_manager(),
+            PackageManager::Zypper
+        );
+        assert_eq!(
+            target("archlinux", "latest").package_manager(),
+            PackageManager::Pacman
+        );
+    }
+
+    #[test]
+    fn test_packaged_versions() {
+        assert_eq!(
+            target("debian", "bookworm").packaged_version(Runtime::Ruby),
+            Some("3.1")
+        );
+        assert_eq!(
+            target("ubuntu", "24.04").packaged_version(Runtime::Perl),
+            Some("5.38")
+        );
+        assert_eq!(
+            target("alpine", "3.20").packaged_version(Runtime::Python),
+            Some("3.12")
+        );
+        assert_eq!(target("fedora", "40").packaged_version(Runtime::Ruby), None);
+    }
+
+    #[test]
+    fn test_unknown_os_is_rejected() {
+        let err = TargetOs::from_config(&OsConfig {
+            os_type: "windows".to_string(),
+            version: "11".to_string(),
+        })
+        .unwrap_err();
+        assert!(matches!(err, GeneratorError::UnsupportedOs(os) if os == "windows"));
+    }
+
+    #[test]
+    fn test_unknown_alpine_release_is_rejected() {
+        let err = TargetOs::from_config(&OsConfig {
+            os_type: "alpine".to_string(),
+            version: "2.7".to_string(),
+        })
+        .unwrap_err();
+        assert!(
+            matches!(err, GeneratorError::UnsupportedOsVersion { version, .. } if version == "2.7")
+        );
+    }
+
+    #[test]
+    fn test_unsafe_version_is_rejected() {
+        for version in [
+            "24.04 AS x",
+            "latest\nRUN id",
+            "22.04@sha256:abc",
+            "$(id)",
+            "",
+        ] {
+            let err = TargetOs::from_config(&OsConfig {
+                os_type: "ubuntu".to_string(),
+                version: version.to_string(),
+            })
+            .unwrap_err();
+            assert!(matches!(err, GeneratorError::UnsupportedOsVersion { .. }));
+        }
+    }
+}
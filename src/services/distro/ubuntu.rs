@@ -0,0 +1,61 @@
+//! Ubuntu release metadata.
+
+pub struct UbuntuRelease {
+    pub version: &'static str,
+    pub codename: &'static str,
+    /// Minor version of the `python3` package
+    pub python: &'static str,
+    /// Minor version of the `ruby` package
+    pub ruby: &'static str,
+    /// Minor version of the `perl` package
+    pub perl: &'static str,
+    /// Major versions with `openjdk-N-jdk-headless` packages
+    pub openjdk: &'static [&'static str],
+}
+
+/// Supported LTS releases, oldest first
+pub const RELEASES: &[UbuntuRelease] = &[
+    UbuntuRelease {
+        version: "20.04",
+        codename: "focal",
+        python: "3.8",
+        ruby: "2.7",
+        perl: "5.30",
+        openjdk: &["8", "11", "17"],
+    },
+    UbuntuRelease {
+        version: "22.04",
+        codename: "jammy",
+        python: "3.10",
+        ruby: "3.0",
+        perl: "5.34",
+        openjdk: &["8", "11", "17", "21"],
+    },
+    UbuntuRelease {
+        version: "24.04",
+        codename: "noble",
+        python: "3.12",
+        ruby: "3.2",
+        perl: "5.38",
+        openjdk: &["8", "11", "17", "21"],
+    },
+];
+
+/// Look up a release by version (`22.04`) or codename (`jammy`)
+pub fn release(version: &str) -> Option<&'static UbuntuRelease> {
+    RELEASES
+        .iter()
+        .find(|r| r.version == version || r.codename == version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_lookup() {
+        assert_eq!(release("22.04").unwrap().ruby, "3.0");
+        assert_eq!(release("noble").unwrap().perl, "5.38");
+        assert!(release("23.10").is_none());
+    }
+}
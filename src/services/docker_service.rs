@@ -1,22 +1,63 @@
+use crate::models::build::{BuildEvent, BuildOptions, PullPolicy};
 use crate::models::container::{ContainerDetail, ContainerInfo, MountInfo, PortMapping};
+use crate::models::image::{ImageDetail, ImageFilters, ImageInfo, ImageLayer, ImageRemoval};
+use crate::services::build_context::BuildContext;
+use crate::services::dockerfile_generator::SSH_PORT_LABEL;
+use crate::services::dockerfile_parser;
 use anyhow::Result;
 use bollard::container::{
     Config as ContainerConfig, CreateContainerOptions, ListContainersOptions,
     RemoveContainerOptions, StartContainerOptions, StopContainerOptions,
 };
-use bollard::image::BuildImageOptions;
+use bollard::image::{
+    BuildImageOptions, BuilderVersion, ListImagesOptions, PruneImagesOptions, RemoveImageOptions,
+    TagImageOptions,
+};
+use bollard::models::{
+    BuildInfo, BuildInfoAux, ImageDeleteResponseItem, ImageInspect, ImageSummary,
+};
 use bollard::Docker;
-use futures_util::stream::StreamExt;
+use futures_util::stream::{self, Stream, StreamExt};
 use std::collections::HashMap;
+use std::sync::Mutex;
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+#[derive(Debug, Error)]
+#[error("Docker daemon is not available: {0}")]
+pub struct DockerUnavailable(String);
 
+/// Talks to the Docker daemon, connecting on first use so the server also
+/// starts (and serves the generator) on hosts without Docker
 pub struct DockerService {
-    docker: Docker,
+    docker: Mutex<Option<Docker>>,
 }
 
 impl DockerService {
-    pub fn new() -> Result<Self> {
-        let docker = Docker::connect_with_local_defaults()?;
-        Ok(Self { docker })
+    pub fn new() -> Self {
+        Self {
+            docker: Mutex::new(None),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_client(docker: Docker) -> Self {
+        Self {
+            docker: Mutex::new(Some(docker)),
+        }
+    }
+
+    /// The daemon connection, retried on every call until it succeeds
+    pub fn client(&self) -> Result<Docker, DockerUnavailable> {
+        let mut docker = self.docker.lock().unwrap();
+        if let Some(docker) = docker.as_ref() {
+            return Ok(docker.clone());
+        }
+
+        let client =
+            Docker::connect_with_local_defaults().map_err(|e| DockerUnavailable(e.to_string()))?;
+        *docker = Some(client.clone());
+        Ok(client)
     }
 
     pub async fn list_containers(&self) -> Result<Vec<ContainerInfo>> {
@@ -25,7 +66,7 @@ impl DockerService {
             ..Default::default()
         });
 
-        let containers = self.docker.list_containers(options).await?;
+        let containers = self.client()?.list_containers(options).await?;
 
         let container_infos: Vec<ContainerInfo> = containers
             .into_iter()
@@ -71,7 +112,7 @@ impl DockerService {
     }
 
     pub async fn get_container(&self, id: &str) -> Result<ContainerDetail> {
-        let container = self.docker.inspect_container(id, None).await?;
+        let container = self.client()?.inspect_container(id, None).await?;
 
         let id = container.id.unwrap_or_default();
         let name = container
@@ -162,14 +203,14 @@ impl DockerService {
     }
 
     pub async fn start_container(&self, id: &str) -> Result<()> {
-        self.docker
+        self.client()?
             .start_container(id, None::<StartContainerOptions<String>>)
             .await?;
         Ok(())
     }
 
     pub async fn stop_container(&self, id: &str) -> Result<()> {
-        self.docker
+        self.client()?
             .stop_container(id, None::<StopContainerOptions>)
             .await?;
         Ok(())
@@ -180,62 +221,166 @@ impl DockerService {
             force: true,
             ..Default::default()
         });
-        self.docker.remove_container(id, options).await?;
+        self.client()?.remove_container(id, options).await?;
         Ok(())
     }
 
-    pub async fn build_image(&self, dockerfile: &str, tag: &str) -> Result<Vec<String>> {
+    pub async fn build_image(
+        &self,
+        context: BuildContext,
+        tag: &str,
+        options: BuildOptions,
+    ) -> Result<Vec<String>> {
+        let mut events = std::pin::pin!(self.build_image_stream(context, tag, options));
+
+        let mut logs = Vec::new();
+        while let Some(event) = events.next().await {
+            if let BuildEvent::Error { message } = event {
+                tracing::error!("Build error: {}", message);
+                return Err(anyhow::anyhow!("Build failed: {}", message));
+            }
+            if let Some(line) = event.log_line() {
+                tracing::info!("Build: {}", line.trim());
+                logs.push(line);
+            }
+        }
+
+        Ok(logs)
+    }
+
+    /// Start a build and return its events as they arrive. The build runs in
+    /// its own task, which stops once the returned stream is dropped.
+    /// `options` must have passed `build_options::validate`.
+    pub fn build_image_stream(
+        &self,
+        context: BuildContext,
+        tag: &str,
+        options: BuildOptions,
+    ) -> impl Stream<Item = BuildEvent> + Send + 'static {
+        // Cache mounts and other BuildKit-only syntax need a BuildKit session
+        let buildkit = uses_buildkit(&context.dockerfile);
+        let base_images = match options.pull {
+            PullPolicy::Never => base_images(&context.dockerfile),
+            PullPolicy::Always | PullPolicy::Missing => Vec::new(),
+        };
         let build_options = BuildImageOptions {
             t: tag.to_string(),
-            rm: true,   // Remove intermediate containers
-            pull: true, // Always pull base image
+            rm: true, // Remove intermediate containers
+            nocache: options.no_cache,
+            // Without `pull` the daemon still fetches base images it doesn't have
+            pull: options.pull == PullPolicy::Always,
+            buildargs: options.build_args.into_iter().collect(),
+            target: options.target.unwrap_or_default(),
+            platform: options.platform.unwrap_or_default(),
+            labels: options.labels.into_iter().collect(),
+            version: if buildkit {
+                BuilderVersion::BuilderBuildKit
+            } else {
+                BuilderVersion::BuilderV1
+            },
+            session: buildkit.then(|| {
+                format!(
+                    "container-helper-{}",
+                    chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+                )
+            }),
             ..Default::default()
         };
 
-        let dockerfile_tar = self.create_dockerfile_tar(dockerfile)?;
+        let docker = self.client();
+        let (sender, receiver) = mpsc::channel(64);
+        tokio::spawn(async move {
+            let docker = match docker {
+                Ok(docker) => docker,
+                Err(e) => {
+                    let message = e.to_string();
+                    forward(&sender, vec![BuildEvent::Error { message }]).await;
+                    return;
+                }
+            };
+
+            for image in base_images {
+                if docker.inspect_image(&image).await.is_err() {
+                    let message = format!(
+                        "Base image {} is not available locally and pulling is disabled",
+                        image
+                    );
+                    forward(&sender, vec![BuildEvent::Error { message }]).await;
+                    return;
+                }
+            }
 
-        let mut stream = self
-            .docker
-            .build_image(build_options, None, Some(dockerfile_tar.into()));
+            let tag = build_options.t.clone();
+            let mut stream = docker.build_image(build_options, None, Some(context.archive.into()));
+            loop {
+                // Dropping the stream closes the connection, which stops the build
+                let build_info = tokio::select! {
+                    _ = sender.closed() => return,
+                    build_info = stream.next() => match build_info {
+                        Some(build_info) => build_info,
+                        None => break,
+                    },
+                };
+                let events = match build_info {
+                    Ok(info) => build_events(info),
+                    Err(e) => vec![BuildEvent::Error {
+                        message: e.to_string(),
+                    }],
+                };
+                if !forward(&sender, events).await {
+                    return;
+                }
+            }
 
-        let mut logs = Vec::new();
-        while let Some(build_info) = stream.next().await {
-            match build_info {
-                Ok(info) => {
-                    if let Some(stream) = info.stream {
-                        tracing::info!("Build: {}", stream.trim());
-                        logs.push(stream);
-                    }
-                    if let Some(error) = info.error {
-                        tracing::error!("Build error: {}", error);
-                        return Err(anyhow::anyhow!("Build failed: {}", error));
-                    }
+            for extra_tag in options.extra_tags {
+                let (repo, tag_name) = split_tag(&extra_tag);
+                let tag_options = TagImageOptions {
+                    repo,
+                    tag: tag_name,
+                };
+                let event = match docker.tag_image(&tag, Some(tag_options)).await {
+                    Ok(()) => BuildEvent::Log {
+                        line: format!("Tagged {}\n", extra_tag),
+                    },
+                    Err(e) => BuildEvent::Error {
+                        message: format!("Failed to tag {}: {}", extra_tag, e),
+                    },
+                };
+                if !forward(&sender, vec![event]).await {
+                    return;
                 }
-                Err(e) => return Err(e.into()),
             }
-        }
+        });
 
-        Ok(logs)
+        stream::unfold(receiver, |mut receiver| async move {
+            receiver.recv().await.map(|event| (event, receiver))
+        })
     }
 
+    /// Start a container and return its ID, with the host port sshd was
+    /// published on when the image runs an SSH server
     pub async fn run_container(
         &self,
         image: &str,
         name: Option<&str>,
         env: Option<Vec<String>>,
         ports: Option<HashMap<String, String>>,
-    ) -> Result<String> {
+    ) -> Result<(String, Option<u16>)> {
+        let docker = self.client()?;
         let mut port_bindings = HashMap::new();
         let mut exposed_ports = HashMap::new();
 
-        if let Some(ports_map) = ports {
+        // Images generated with SSH enabled record the sshd port in a label
+        let ssh_port = docker
+            .inspect_image(image)
+            .await?
+            .config
+            .and_then(|config| config.labels)
+            .and_then(|labels| labels.get(SSH_PORT_LABEL)?.parse::<u16>().ok());
+
+        if let Some(ports_map) = with_ssh_port(ports, ssh_port) {
             for (container_port, host_port) in ports_map {
-                // Docker requires port keys to include protocol (e.g., "8080/tcp")
-                let port_key = if container_port.contains('/') {
-                    container_port
-                } else {
-                    format!("{}/tcp", container_port)
-                };
+                let port_key = port_key(container_port);
 
                 // Add to exposed ports
                 exposed_ports.insert(port_key.clone(), HashMap::new());
@@ -271,36 +416,598 @@ impl DockerService {
             ..Default::default()
         });
 
-        let container = self.docker.create_container(options, config).await?;
+        let container = docker.create_container(options, config).await?;
 
-        self.docker
+        docker
             .start_container(&container.id, None::<StartContainerOptions<String>>)
             .await?;
 
-        Ok(container.id)
+        // The daemon picks the host port when it was left empty
+        let ssh_host_port = match ssh_port {
+            Some(ssh_port) => {
+                let detail = self.get_container(&container.id).await?;
+                published_port(&detail.ports, ssh_port)
+            }
+            None => None,
+        };
+
+        Ok((container.id, ssh_host_port))
     }
 
-    fn create_dockerfile_tar(&self, dockerfile_content: &str) -> Result<Vec<u8>> {
-        use tar::Builder;
+    pub async fn list_images(&self, filters: &ImageFilters) -> Result<Vec<ImageInfo>> {
+        let options = ListImagesOptions {
+            all: filters.all,
+            filters: list_filters(filters),
+            ..Default::default()
+        };
+
+        let images = self.client()?.list_images(Some(options)).await?;
+        Ok(images.into_iter().map(image_info).collect())
+    }
 
-        let mut archive = Builder::new(Vec::new());
-        let dockerfile_bytes = dockerfile_content.as_bytes();
+    pub async fn get_image(&self, id: &str) -> Result<ImageDetail> {
+        let image = self.client()?.inspect_image(id).await?;
+        Ok(image_detail(image))
+    }
 
-        let mut header = tar::Header::new_gnu();
-        header.set_path("Dockerfile")?;
-        header.set_size(dockerfile_bytes.len() as u64);
-        header.set_mode(0o644);
-        header.set_cksum();
+    pub async fn image_history(&self, id: &str) -> Result<Vec<ImageLayer>> {
+        let history = self.client()?.image_history(id).await?;
 
-        archive.append(&header, dockerfile_bytes)?;
-        archive.finish()?;
+        let layers = history
+            .into_iter()
+            .map(|item| ImageLayer {
+                // Layers pulled from a registry have no local ID
+                id: if item.id == "<missing>" {
+                    String::new()
+                } else {
+                    item.id
+                },
+                created: item.created,
+                created_by: item.created_by,
+                tags: item.tags,
+                size: item.size,
+                comment: item.comment,
+            })
+            .collect();
 
-        Ok(archive.into_inner()?)
+        Ok(layers)
+    }
+
+    /// Add `reference` (`repo[:tag]`) as a tag of image `id`
+    pub async fn tag_image(&self, id: &str, reference: &str) -> Result<()> {
+        let (repo, tag) = split_tag(reference);
+        self.client()?
+            .tag_image(id, Some(TagImageOptions { repo, tag }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_image(&self, id: &str, force: bool) -> Result<ImageRemoval> {
+        let options = RemoveImageOptions {
+            force,
+            ..Default::default()
+        };
+
+        let removed = self.client()?.remove_image(id, Some(options), None).await?;
+        Ok(image_removal(removed, 0))
+    }
+
+    /// Delete dangling images, optionally only those with a matching label
+    pub async fn prune_images(&self, label: Option<&str>) -> Result<ImageRemoval> {
+        let mut filters = HashMap::from([("dangling".to_string(), vec!["true".to_string()])]);
+        if let Some(label) = label {
+            filters.insert("label".to_string(), vec![label.to_string()]);
+        }
+
+        let response = self
+            .client()?
+            .prune_images(Some(PruneImagesOptions { filters }))
+            .await?;
+        Ok(image_removal(
+            response.images_deleted.unwrap_or_default(),
+            response.space_reclaimed.unwrap_or_default(),
+        ))
+    }
+}
+
+/// Docker requires port keys to include protocol (e.g., "8080/tcp")
+fn port_key(container_port: String) -> String {
+    if container_port.contains('/') {
+        container_port
+    } else {
+        format!("{}/tcp", container_port)
+    }
+}
+
+/// Publish the sshd port on a host port chosen by the daemon unless the caller
+/// mapped it already. Reusing the container port could clash with the host's
+/// own sshd on port 22.
+fn with_ssh_port(
+    ports: Option<HashMap<String, String>>,
+    ssh_port: Option<u16>,
+) -> Option<HashMap<String, String>> {
+    let Some(ssh_port) = ssh_port else {
+        return ports;
+    };
+
+    let mut ports = ports.unwrap_or_default();
+    let ssh_key = format!("{}/tcp", ssh_port);
+    if !ports.keys().any(|key| port_key(key.clone()) == ssh_key) {
+        ports.insert(ssh_port.to_string(), String::new());
+    }
+    Some(ports)
+}
+
+/// Host port a TCP container port is published on
+fn published_port(ports: &[PortMapping], container_port: u16) -> Option<u16> {
+    ports
+        .iter()
+        .find(|port| port.private_port == container_port && port.protocol == "tcp")
+        .and_then(|port| port.public_port)
+}
+
+/// Daemon-side filters for listing images
+fn list_filters(filters: &ImageFilters) -> HashMap<String, Vec<String>> {
+    let mut map = HashMap::new();
+    if let Some(reference) = &filters.reference {
+        map.insert("reference".to_string(), vec![reference.clone()]);
+    }
+    if let Some(dangling) = filters.dangling {
+        map.insert("dangling".to_string(), vec![dangling.to_string()]);
+    }
+    if let Some(label) = &filters.label {
+        map.insert("label".to_string(), vec![label.clone()]);
+    }
+    map
+}
+
+fn image_info(image: ImageSummary) -> ImageInfo {
+    // Untagged images are listed with a `<none>:<none>` placeholder
+    let tags: Vec<String> = image
+        .repo_tags
+        .into_iter()
+        .filter(|tag| tag != "<none>:<none>")
+        .collect();
+
+    ImageInfo {
+        id: image.id,
+        dangling: tags.is_empty(),
+        tags,
+        created: image.created,
+        size: image.size,
+        containers: image.containers,
+        labels: image.labels,
     }
 }
 
+fn image_detail(image: ImageInspect) -> ImageDetail {
+    let config = image.config.unwrap_or_default();
+
+    let created = image.created.map(|dt| dt.timestamp()).unwrap_or_default();
+
+    let mut exposed_ports: Vec<String> = config
+        .exposed_ports
+        .unwrap_or_default()
+        .into_keys()
+        .collect();
+    exposed_ports.sort();
+
+    ImageDetail {
+        id: image.id.unwrap_or_default(),
+        tags: image.repo_tags.unwrap_or_default(),
+        digests: image.repo_digests.unwrap_or_default(),
+        created,
+        size: image.size.unwrap_or_default(),
+        architecture: image.architecture.unwrap_or_default(),
+        os: image.os.unwrap_or_default(),
+        author: image.author.unwrap_or_default(),
+        user: config.user.unwrap_or_default(),
+        working_dir: config.working_dir.unwrap_or_default(),
+        entrypoint: config.entrypoint.unwrap_or_default(),
+        cmd: config.cmd.unwrap_or_default(),
+        env: config.env.unwrap_or_default(),
+        exposed_ports,
+        labels: config.labels.unwrap_or_default(),
+        layers: image
+            .root_fs
+            .and_then(|fs| fs.layers)
+            .map_or(0, |layers| layers.len()),
+    }
+}
+
+fn image_removal(items: Vec<ImageDeleteResponseItem>, space_reclaimed: i64) -> ImageRemoval {
+    let mut removal = ImageRemoval {
+        space_reclaimed,
+        ..Default::default()
+    };
+    for item in items {
+        removal.untagged.extend(item.untagged);
+        removal.deleted.extend(item.deleted);
+    }
+    removal
+}
+
 impl Default for DockerService {
     fn default() -> Self {
-        Self::new().expect("Failed to connect to Docker")
+        Self::new()
+    }
+}
+
+/// Send `events` on to the client. False once the client is gone or the
+/// build failed, either of which ends the build.
+async fn forward(sender: &mpsc::Sender<BuildEvent>, events: Vec<BuildEvent>) -> bool {
+    for event in events {
+        let failed = matches!(event, BuildEvent::Error { .. });
+        if sender.send(event).await.is_err() || failed {
+            return false;
+        }
+    }
+    true
+}
+
+/// Images the Dockerfile's stages start from, leaving out `scratch`, earlier
+/// stages and references that depend on build arguments
+fn base_images(dockerfile: &str) -> Vec<String> {
+    let mut stages: Vec<String> = Vec::new();
+    let mut images: Vec<String> = Vec::new();
+    let instructions = dockerfile_parser::parse(dockerfile).unwrap_or_default();
+    for instruction in instructions.iter().filter(|i| i.keyword == "FROM") {
+        let (image, name) = dockerfile_parser::from_stage(&instruction.arguments);
+        let external = image != "scratch"
+            && !image.contains('$')
+            && !stages.contains(&image.to_ascii_lowercase());
+        if external && !images.iter().any(|i| i == image) {
+            images.push(image.to_string());
+        }
+        stages.extend(name.map(str::to_ascii_lowercase));
+    }
+    images
+}
+
+/// Split `repo[:tag]` for the tag API, defaulting the tag to `latest`
+fn split_tag(reference: &str) -> (String, String) {
+    // A colon before the last slash belongs to a registry port
+    let name_start = reference.rfind('/').map_or(0, |slash| slash + 1);
+    match reference[name_start..].rfind(':') {
+        Some(colon) => {
+            let colon = name_start + colon;
+            (
+                reference[..colon].to_string(),
+                reference[colon + 1..].to_string(),
+            )
+        }
+        None => (reference.to_string(), "latest".to_string()),
+    }
+}
+
+/// Translate one message from the Docker daemon into build events
+fn build_events(info: BuildInfo) -> Vec<BuildEvent> {
+    let mut events = Vec::new();
+    if let Some(line) = info.stream {
+        events.push(BuildEvent::Log { line });
+    }
+    if let Some(status) = info.status {
+        let detail = info.progress_detail.unwrap_or_default();
+        events.push(BuildEvent::Progress {
+            id: info.id,
+            status,
+            progress: info.progress,
+            current: detail.current,
+            total: detail.total,
+        });
+    }
+    match info.aux {
+        Some(BuildInfoAux::Default(image)) => {
+            if let Some(id) = image.id {
+                events.push(BuildEvent::ImageId { id });
+            }
+        }
+        // BuildKit reports progress as status updates instead of a log stream
+        Some(BuildInfoAux::BuildKit(status)) => {
+            for vertex in status.vertexes {
+                if !vertex.error.is_empty() {
+                    events.push(BuildEvent::Error {
+                        message: vertex.error,
+                    });
+                } else if vertex.completed.is_some() {
+                    events.push(BuildEvent::Step {
+                        name: vertex.name,
+                        cached: vertex.cached,
+                    });
+                }
+            }
+            for status in status.statuses {
+                events.push(BuildEvent::Progress {
+                    id: Some(status.id),
+                    status: status.name,
+                    progress: None,
+                    current: Some(status.current),
+                    total: (status.total > 0).then_some(status.total),
+                });
+            }
+            for log in status.logs {
+                events.push(BuildEvent::Log {
+                    line: String::from_utf8_lossy(&log.msg).into_owned(),
+                });
+            }
+        }
+        None => {}
+    }
+    if let Some(message) = info.error {
+        events.push(BuildEvent::Error { message });
+    }
+    events
+}
+
+/// Whether `dockerfile` uses syntax only the BuildKit builder understands
+fn uses_buildkit(dockerfile: &str) -> bool {
+    dockerfile_parser::syntax_directive(dockerfile).is_some() || dockerfile.contains("--mount=")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bollard::moby::buildkit::v1::{StatusResponse, Vertex, VertexLog};
+    use bollard::models::{ImageConfig, ImageId, ImageInspectRootFs, ProgressDetail};
+
+    #[test]
+    fn test_with_ssh_port_adds_mapping() {
+        let ports = with_ssh_port(None, Some(2222)).unwrap();
+        assert_eq!(ports.get("2222").map(String::as_str), Some(""));
+
+        assert_eq!(with_ssh_port(None, None), None);
+    }
+
+    #[test]
+    fn test_with_ssh_port_22_uses_ephemeral_host_port() {
+        let requested = HashMap::from([("8080".to_string(), "8080".to_string())]);
+        let ports = with_ssh_port(Some(requested), Some(22)).unwrap();
+        // An empty host port leaves the host's port 22 to its own sshd
+        assert_eq!(ports.get("22").map(String::as_str), Some(""));
+        assert_eq!(ports.get("8080").map(String::as_str), Some("8080"));
+    }
+
+    #[test]
+    fn test_published_port() {
+        let ports = vec![
+            PortMapping {
+                private_port: 22,
+                public_port: Some(32768),
+                protocol: "tcp".to_string(),
+            },
+            PortMapping {
+                private_port: 53,
+                public_port: Some(5353),
+                protocol: "udp".to_string(),
+            },
+        ];
+        assert_eq!(published_port(&ports, 22), Some(32768));
+        assert_eq!(published_port(&ports, 53), None);
+        assert_eq!(published_port(&ports, 2222), None);
+    }
+
+    #[test]
+    fn test_with_ssh_port_keeps_explicit_mapping() {
+        let explicit = HashMap::from([("2222/tcp".to_string(), "30022".to_string())]);
+        let ports = with_ssh_port(Some(explicit.clone()), Some(2222)).unwrap();
+        assert_eq!(ports, explicit);
+    }
+
+    #[test]
+    fn test_uses_buildkit() {
+        assert!(uses_buildkit(
+            "# syntax=docker/dockerfile:1\nFROM debian:bookworm"
+        ));
+        assert!(uses_buildkit(
+            "FROM debian:bookworm\nRUN --mount=type=cache,target=/var/cache/apt apt-get update"
+        ));
+        assert!(!uses_buildkit("FROM debian:bookworm\nRUN apt-get update"));
+    }
+
+    #[test]
+    fn test_build_events_from_classic_builder() {
+        let events = build_events(BuildInfo {
+            stream: Some("Step 1/2 : FROM debian:bookworm\n".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            events,
+            [BuildEvent::Log {
+                line: "Step 1/2 : FROM debian:bookworm\n".to_string()
+            }]
+        );
+
+        let events = build_events(BuildInfo {
+            id: Some("bookworm".to_string()),
+            status: Some("Downloading".to_string()),
+            progress: Some("[==>    ]".to_string()),
+            progress_detail: Some(ProgressDetail {
+                current: Some(10),
+                total: Some(40),
+            }),
+            ..Default::default()
+        });
+        assert_eq!(
+            events,
+            [BuildEvent::Progress {
+                id: Some("bookworm".to_string()),
+                status: "Downloading".to_string(),
+                progress: Some("[==>    ]".to_string()),
+                current: Some(10),
+                total: Some(40),
+            }]
+        );
+
+        let events = build_events(BuildInfo {
+            aux: Some(BuildInfoAux::Default(ImageId {
+                id: Some("sha256:abc".to_string()),
+            })),
+            ..Default::default()
+        });
+        assert_eq!(
+            events,
+            [BuildEvent::ImageId {
+                id: "sha256:abc".to_string()
+            }]
+        );
+
+        let events = build_events(BuildInfo {
+            error: Some("exit code 1".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            events,
+            [BuildEvent::Error {
+                message: "exit code 1".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_build_events_from_buildkit() {
+        let status = StatusResponse {
+            vertexes: vec![
+                Vertex {
+                    name: "[1/2] FROM debian:bookworm".to_string(),
+                    cached: true,
+                    completed: Some(Default::default()),
+                    ..Default::default()
+                },
+                Vertex {
+                    name: "[2/2] RUN apt-get update".to_string(),
+                    ..Default::default()
+                },
+            ],
+            logs: vec![VertexLog {
+                msg: b"Get:1 http://deb.debian.org bookworm InRelease\n".to_vec(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let events = build_events(BuildInfo {
+            aux: Some(BuildInfoAux::BuildKit(status)),
+            ..Default::default()
+        });
+        assert_eq!(
+            events,
+            [
+                BuildEvent::Step {
+                    name: "[1/2] FROM debian:bookworm".to_string(),
+                    cached: true,
+                },
+                BuildEvent::Log {
+                    line: "Get:1 http://deb.debian.org bookworm InRelease\n".to_string()
+                },
+            ]
+        );
+
+        let status = StatusResponse {
+            vertexes: vec![Vertex {
+                error: "process did not complete successfully".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let events = build_events(BuildInfo {
+            aux: Some(BuildInfoAux::BuildKit(status)),
+            ..Default::default()
+        });
+        assert!(matches!(&events[..], [BuildEvent::Error { .. }]));
+    }
+
+    #[test]
+    fn test_base_images() {
+        let dockerfile = "FROM --platform=$BUILDPLATFORM rust:1 AS build\n\
+                          FROM build AS test\n\
+                          FROM ${BASE}\n\
+                          FROM scratch\n\
+                          FROM debian:bookworm\n\
+                          COPY --from=build /app /app\n";
+        assert_eq!(base_images(dockerfile), ["rust:1", "debian:bookworm"]);
+    }
+
+    #[test]
+    fn test_split_tag() {
+        assert_eq!(split_tag("app"), ("app".to_string(), "latest".to_string()));
+        assert_eq!(
+            split_tag("team/app:1.2"),
+            ("team/app".to_string(), "1.2".to_string())
+        );
+        assert_eq!(
+            split_tag("localhost:5000/app"),
+            ("localhost:5000/app".to_string(), "latest".to_string())
+        );
+    }
+
+    #[test]
+    fn test_list_filters() {
+        let filters = list_filters(&ImageFilters {
+            reference: Some("containerhelper*".to_string()),
+            dangling: Some(false),
+            ..Default::default()
+        });
+        assert_eq!(filters["reference"], ["containerhelper*"]);
+        assert_eq!(filters["dangling"], ["false"]);
+        assert!(!filters.contains_key("label"));
+    }
+
+    #[test]
+    fn test_image_info() {
+        let info = image_info(ImageSummary {
+            id: "sha256:abc".to_string(),
+            repo_tags: vec!["<none>:<none>".to_string()],
+            size: 1024,
+            containers: 2,
+            ..Default::default()
+        });
+        assert!(info.tags.is_empty());
+        assert!(info.dangling);
+        assert_eq!(info.containers, 2);
+    }
+
+    #[test]
+    fn test_image_detail() {
+        let detail = image_detail(ImageInspect {
+            id: Some("sha256:abc".to_string()),
+            created: "2024-06-01T12:00:00Z".parse().ok(),
+            config: Some(ImageConfig {
+                exposed_ports: Some(HashMap::from([
+                    ("8080/tcp".to_string(), HashMap::new()),
+                    ("22/tcp".to_string(), HashMap::new()),
+                ])),
+                cmd: Some(vec!["/bin/bash".to_string()]),
+                ..Default::default()
+            }),
+            root_fs: Some(ImageInspectRootFs {
+                layers: Some(vec!["sha256:1".to_string(), "sha256:2".to_string()]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        assert_eq!(detail.created, 1717243200);
+        assert_eq!(detail.exposed_ports, ["22/tcp", "8080/tcp"]);
+        assert_eq!(detail.cmd, ["/bin/bash"]);
+        assert_eq!(detail.layers, 2);
+        assert!(detail.tags.is_empty());
+    }
+
+    #[test]
+    fn test_image_removal() {
+        let removal = image_removal(
+            vec![
+                ImageDeleteResponseItem {
+                    untagged: Some("app:1".to_string()),
+                    deleted: None,
+                },
+                ImageDeleteResponseItem {
+                    untagged: None,
+                    deleted: Some("sha256:abc".to_string()),
+                },
+            ],
+            512,
+        );
+        assert_eq!(removal.untagged, ["app:1"]);
+        assert_eq!(removal.deleted, ["sha256:abc"]);
+        assert_eq!(removal.space_reclaimed, 512);
     }
 }
@@ -1,203 +1,758 @@
-use crate::models::config::{EnvironmentConfig, Language, OsConfig};
+use crate::models::config::{
+    DevUser, EnvironmentConfig, Language, MultiStageConfig, RuntimeImage, SshConfig, SystemPackage,
+};
+use crate::models::dockerfile::{
+    is_label_key, is_plain_word, is_variable_name, print_lines, write_file, CacheMount, Dockerfile,
+    Instruction, Section,
+};
+use crate::services::distro::TargetOs;
+use crate::services::languages;
+use crate::services::package_manager::{merge_packages, InstallPlan, PackageManager};
 
-pub fn generate_dockerfile(config: &EnvironmentConfig) -> String {
-    let mut lines = Vec::new();
+#[derive(Debug, thiserror::Error)]
+pub enum GeneratorError {
+    #[error("Unsupported language: {0}")]
+    UnsupportedLanguage(String),
+    #[error("Unsupported OS type: {0}")]
+    UnsupportedOs(String),
+    #[error("Unsupported {os} version: {version}")]
+    UnsupportedOsVersion { os: String, version: String },
+    #[error("{language} {version} is not available on {os}")]
+    UnavailableVersion {
+        language: String,
+        version: String,
+        os: String,
+    },
+    #[error("Unsupported {language} option: {value}")]
+    UnsupportedOption { language: String, value: String },
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+}
+
+/// Image label recording the port sshd listens on, so containers can be
+/// started with that port published automatically
+pub const SSH_PORT_LABEL: &str = "containerhelper.ssh.port";
+
+pub fn generate_dockerfile(config: &EnvironmentConfig) -> Result<String, GeneratorError> {
+    Ok(build_dockerfile(config)?.render())
+}
+
+/// Build the Dockerfile model for `config`
+pub fn build_dockerfile(config: &EnvironmentConfig) -> Result<Dockerfile, GeneratorError> {
+    let mut dockerfile = Dockerfile::default();
+    let target = TargetOs::from_config(&config.os)?;
+    let package_manager = target.package_manager();
+
+    if let Some(multi_stage) = &config.multi_stage {
+        validate_multi_stage(config, multi_stage)?;
+    }
+    if let Some(user) = &config.user {
+        validate_user(user)?;
+    }
+    if let Some(ssh) = config.ssh.as_ref().filter(|s| s.enabled) {
+        validate_ssh(ssh, config.user.as_ref())?;
+    }
+    validate_extras(config)?;
 
-    // Generate FROM instruction
-    let os_image = get_os_image(&config.os);
-    lines.push(format!("FROM {}", os_image));
-    lines.push(String::new());
+    // Cache mounts are built into the BuildKit frontend bundled with the
+    // daemon, so no `# syntax` directive has to pull a newer one
+    let cached = config.build_cache;
+
+    // Generate FROM instruction; toolchains live in the builder stage of multi-stage builds
+    let image = target.image();
+    let mut from = Section::new();
+    if image.ends_with(":latest") {
+        // Rolling releases only publish a moving tag
+        from = from.comment("hadolint ignore=DL3007");
+    }
+    dockerfile.push(from.instruction(Instruction::From {
+        image,
+        alias: config.multi_stage.as_ref().map(|_| "builder".to_string()),
+    }));
 
     // Set working directory
-    lines.push("WORKDIR /app".to_string());
-    lines.push(String::new());
+    dockerfile.push(Section::new().instruction(Instruction::Workdir("/app".to_string())));
 
-    // Install languages and their dependencies
+    if !config.args.is_empty() {
+        dockerfile.push(
+            Section::new().comment("Build arguments").instructions(
+                config
+                    .args
+                    .iter()
+                    .map(|(name, value)| Instruction::Arg(name.clone(), value.clone())),
+            ),
+        );
+    }
+
+    let keep_cache = package_manager.keep_cache();
+    if cached && !keep_cache.is_empty() {
+        dockerfile.push(
+            Section::new()
+                .comment("Keep downloaded packages in the BuildKit cache")
+                .instruction(Instruction::run(
+                    keep_cache.iter().map(|c| c.to_string()).collect(),
+                )),
+        );
+    }
+
+    // Resolve every install plan up front so their distro packages can share one layer
+    let registry = languages::registry();
+    let mut language_plans = Vec::new();
+    let mut build_caches = Vec::new();
     for language in &config.languages {
-        let language_commands = get_language_install_commands(language, &config.os.os_type);
+        let installer = registry
+            .get(&language.name)
+            .ok_or_else(|| GeneratorError::UnsupportedLanguage(language.name.clone()))?;
+        validate_language(language, &target)?;
+        let caches: Vec<CacheMount> = if cached {
+            installer
+                .cache_dirs()
+                .iter()
+                .map(|d| CacheMount::new(*d))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        build_caches.extend(caches.iter().cloned());
+        language_plans.push((language, installer.install_plan(language, &target)?, caches));
+    }
+
+    let ssh = config.ssh.as_ref().filter(|s| s.enabled);
+    let login_user = config.user.as_ref();
+    let mut user_plan = config
+        .user
+        .as_ref()
+        .map(|u| get_user_setup_plan(u, &target));
+    let mut ssh_plan = ssh.map(|s| get_ssh_install_plan(s, login_user, &target));
+    let mut extras_plan =
+        InstallPlan::packages(&resolve_packages(&config.packages, package_manager));
+
+    let packages = merge_packages(
+        language_plans
+            .iter_mut()
+            .map(|(_, plan, _)| plan)
+            .chain(user_plan.as_mut())
+            .chain(ssh_plan.as_mut())
+            .chain([&mut extras_plan]),
+    );
+    if !packages.is_empty() {
+        dockerfile.push(
+            Section::new()
+                .comment("Install system packages")
+                .instructions(
+                    InstallPlan::packages(&packages).instructions(package_manager, cached),
+                ),
+        );
+    }
 
-        if !language_commands.is_empty() {
-            lines.push(format!("# Install {} {}", language.name, language.version));
-            lines.extend(language_commands);
-            lines.push(String::new());
+    // Install languages and their dependencies
+    for (language, plan, caches) in &language_plans {
+        let mut instructions = plan.instructions(package_manager, cached);
+        if instructions.is_empty() {
+            continue;
+        }
+        if let Some(Instruction::Run { mounts, .. }) = instructions.first_mut() {
+            mounts.extend(caches.iter().cloned());
         }
+        dockerfile.push(
+            Section::new()
+                .comment(format!("Install {} {}", language.name, language.version))
+                .instructions(instructions),
+        );
     }
 
-    // Add SSH server if enabled
-    if let Some(ssh) = &config.ssh {
-        if ssh.enabled {
-            let ssh_commands = get_ssh_install_commands(&config.os.os_type);
-            lines.push("# Install and configure SSH server".to_string());
-            lines.extend(ssh_commands);
-            lines.push(String::new());
-            lines.push(format!("EXPOSE {}", ssh.port));
-            lines.push(String::new());
-
-            // Add entrypoint script to set password at runtime
-            lines.push("# Create entrypoint script to set password securely".to_string());
-            lines.push("RUN echo '#!/bin/sh' > /entrypoint.sh && \\".to_string());
-            lines.push(
-                "    echo 'if [ -n \"$ROOT_PASSWORD\" ]; then' >> /entrypoint.sh && \\".to_string(),
-            );
-            lines.push(
-                "    echo '  echo \"root:$ROOT_PASSWORD\" | chpasswd' >> /entrypoint.sh && \\"
-                    .to_string(),
-            );
-            lines.push("    echo 'fi' >> /entrypoint.sh && \\".to_string());
-            lines.push("    echo 'exec \"$@\"' >> /entrypoint.sh && \\".to_string());
-            lines.push("    chmod +x /entrypoint.sh".to_string());
-            lines.push(String::new());
-            lines.push("ENTRYPOINT [\"/entrypoint.sh\"]".to_string());
-            lines.push(String::new());
+    // Labels and environment belong to the final image
+    let metadata = get_metadata_section(config);
+
+    if let Some(multi_stage) = &config.multi_stage {
+        dockerfile
+            .sections
+            .extend(get_runtime_stage(multi_stage, build_caches, metadata));
+        return Ok(dockerfile);
+    }
+
+    // Create the development user and hand it the working directory
+    if let (Some(user), Some(plan)) = (&config.user, &user_plan) {
+        dockerfile.push(
+            Section::new()
+                .comment(format!("Create development user {}", user.name))
+                .instructions(plan.instructions(package_manager, cached)),
+        );
+    }
+
+    // Add SSH server if enabled; logins go to the development user when there is one
+    if let (Some(ssh), Some(plan)) = (ssh, &ssh_plan) {
+        dockerfile.push(
+            Section::new()
+                .comment("Install and configure SSH server")
+                .instructions(plan.instructions(package_manager, cached)),
+        );
+        dockerfile.push(
+            Section::new()
+                .instruction(Instruction::Expose(ssh.port))
+                .instruction(Instruction::Label(
+                    SSH_PORT_LABEL.to_string(),
+                    ssh.port.to_string(),
+                )),
+        );
+
+        if !ssh.disable_password_auth {
+            dockerfile
+                .sections
+                .extend(get_password_entrypoint(login_user));
         }
     }
 
+    if let Some(metadata) = metadata {
+        dockerfile.push(metadata);
+    }
+
     // Add common helpful commands
-    lines.push("# Copy application files".to_string());
-    lines.push("# COPY . .".to_string());
-    lines.push(String::new());
-    lines.push("# Set default command".to_string());
+    dockerfile.push(
+        Section::new()
+            .comment("Copy application files")
+            .comment("COPY . ."),
+    );
+
+    // sshd has to start as root, so SSH images keep root as the default user
+    if let Some(user) = config.user.as_ref().filter(|_| ssh.is_none()) {
+        dockerfile.push(Section::new().instruction(Instruction::User(user.name.clone())));
+    }
 
     // Use sshd if SSH is enabled, otherwise bash
-    if config.ssh.as_ref().is_some_and(|s| s.enabled) {
-        lines.push("CMD [\"/usr/sbin/sshd\", \"-D\"]".to_string());
+    let command = if ssh.is_some() {
+        vec!["/usr/sbin/sshd", "-D"]
     } else {
-        lines.push("CMD [\"/bin/bash\"]".to_string());
+        vec!["/bin/bash"]
+    };
+    dockerfile.push(
+        Section::new()
+            .comment("Set default command")
+            .instruction(Instruction::Cmd(
+                command.into_iter().map(String::from).collect(),
+            )),
+    );
+
+    Ok(dockerfile)
+}
+
+fn validate_multi_stage(
+    config: &EnvironmentConfig,
+    multi_stage: &MultiStageConfig,
+) -> Result<(), GeneratorError> {
+    if config.ssh.as_ref().is_some_and(|s| s.enabled) {
+        return Err(GeneratorError::InvalidConfig(
+            "SSH is only supported for single-stage development images".to_string(),
+        ));
     }
 
-    lines.join("\n")
+    if config.user.is_some() {
+        return Err(GeneratorError::InvalidConfig(
+            "A development user is only supported for single-stage development images".to_string(),
+        ));
+    }
+
+    // A line break would end the instruction and start a new one
+    let mut commands = multi_stage
+        .build_command
+        .iter()
+        .chain(&multi_stage.artifacts);
+    if commands.any(|c| c.chars().any(char::is_control)) {
+        return Err(GeneratorError::InvalidConfig(
+            "Build commands and artifacts cannot contain control characters".to_string(),
+        ));
+    }
+
+    if multi_stage.artifacts.is_empty() {
+        return Err(GeneratorError::InvalidConfig(
+            "Multi-stage builds need at least one artifact".to_string(),
+        ));
+    }
+
+    // COPY would read a leading dash as one of its flags
+    if let Some(artifact) = multi_stage
+        .artifacts
+        .iter()
+        .find(|a| a.is_empty() || a.starts_with('-'))
+    {
+        return Err(GeneratorError::InvalidConfig(format!(
+            "Invalid artifact path: {}",
+            artifact
+        )));
+    }
+
+    Ok(())
 }
 
-fn get_os_image(os: &OsConfig) -> String {
-    match os.os_type.as_str() {
-        "ubuntu" => format!("ubuntu:{}", os.version),
-        "debian" => format!("debian:{}", os.version),
-        "alpine" => "alpine:latest".to_string(),
-        _ => "ubuntu:22.04".to_string(),
+fn validate_user(user: &DevUser) -> Result<(), GeneratorError> {
+    let mut chars = user.name.chars();
+    let valid_name = user.name.len() <= 32
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+    if !valid_name || user.name == "root" {
+        return Err(GeneratorError::InvalidConfig(format!(
+            "Invalid user name: {}",
+            user.name
+        )));
+    }
+
+    if user.uid == 0 || user.gid == 0 {
+        return Err(GeneratorError::InvalidConfig(
+            "The development user needs a non-zero UID and GID".to_string(),
+        ));
+    }
+
+    if let Some(shell) = &user.shell {
+        let valid_shell = shell.starts_with('/')
+            && shell
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '_' | '-' | '.'));
+        if !valid_shell {
+            return Err(GeneratorError::InvalidConfig(format!(
+                "Invalid shell: {}",
+                shell
+            )));
+        }
     }
+
+    Ok(())
 }
 
-fn get_ssh_install_commands(os_type: &str) -> Vec<String> {
-    let is_alpine = os_type == "alpine";
-
-    if is_alpine {
-        vec![
-            "RUN apk update && \\".to_string(),
-            "    apk add --no-cache openssh && \\".to_string(),
-            "    ssh-keygen -A && \\".to_string(),
-            "    sed -i \"s/#PermitRootLogin.*/PermitRootLogin yes/\" /etc/ssh/sshd_config && \\".to_string(),
-            "    sed -i \"s/#PasswordAuthentication.*/PasswordAuthentication yes/\" /etc/ssh/sshd_config".to_string(),
-        ]
-    } else {
-        // Debian/Ubuntu
-        vec![
-            "RUN apt-get update && \\".to_string(),
-            "    DEBIAN_FRONTEND=noninteractive apt-get install -y openssh-server && \\".to_string(),
-            "    mkdir -p /var/run/sshd && \\".to_string(),
-            "    sed -i \"s/#\\?PermitRootLogin.*/PermitRootLogin yes/\" /etc/ssh/sshd_config && \\".to_string(),
-            "    sed -i \"s/#\\?PasswordAuthentication.*/PasswordAuthentication yes/\" /etc/ssh/sshd_config && \\".to_string(),
-            "    rm -rf /var/lib/apt/lists/*".to_string(),
-        ]
+/// Installers interpolate versions and options into shell commands and
+/// URLs, so anything but plain words is rejected before they see it
+fn validate_language(language: &Language, target: &TargetOs) -> Result<(), GeneratorError> {
+    if !is_plain_word(&language.version) {
+        return Err(GeneratorError::UnavailableVersion {
+            language: language.name.clone(),
+            version: language.version.clone(),
+            os: target.describe(),
+        });
     }
+
+    let options = &language.options;
+    let values = options
+        .distribution
+        .iter()
+        .chain(&options.tools)
+        .chain(&options.extensions)
+        .chain(&options.components)
+        .chain(&options.targets);
+    for value in values {
+        if !is_plain_word(value) {
+            return Err(GeneratorError::UnsupportedOption {
+                language: language.name.clone(),
+                value: value.clone(),
+            });
+        }
+    }
+
+    Ok(())
 }
 
-fn get_language_install_commands(language: &Language, os_type: &str) -> Vec<String> {
-    let is_alpine = os_type == "alpine";
-    let package_manager = if is_alpine { "apk" } else { "apt-get" };
-    let update_cmd = if is_alpine {
-        "apk update"
-    } else {
-        "apt-get update"
-    };
-    let install_flag = if is_alpine { "add" } else { "install -y" };
-
-    match language.name.as_str() {
-        "python" => {
-            if is_alpine {
-                vec![
-                    format!("RUN {} && \\", update_cmd),
-                    format!(
-                        "    {} {} python3 py3-pip && \\",
-                        package_manager, install_flag
-                    ),
-                    "    rm -rf /var/cache/apk/*".to_string(),
-                ]
-            } else {
-                vec![
-                    format!("RUN {} && \\", update_cmd),
-                    format!(
-                        "    {} {} software-properties-common && \\",
-                        package_manager, install_flag
-                    ),
-                    "    add-apt-repository ppa:deadsnakes/ppa -y && \\".to_string(),
-                    format!("    {} update && \\", package_manager),
-                    format!(
-                        "    {} {} python{} python{}-pip && \\",
-                        package_manager, install_flag, language.version, language.version
-                    ),
-                    "    rm -rf /var/lib/apt/lists/*".to_string(),
-                ]
+/// Package names and instruction keys end up in shell commands and
+/// Dockerfile syntax, so only accept characters that are safe in both
+fn validate_extras(config: &EnvironmentConfig) -> Result<(), GeneratorError> {
+    for package in &config.packages {
+        let names: Vec<&String> = match package {
+            SystemPackage::Name(name) => vec![name],
+            SystemPackage::PerManager(names) => {
+                if let Some(manager) = names
+                    .keys()
+                    .find(|m| !PackageManager::ALL.iter().any(|pm| pm.name() == *m))
+                {
+                    return Err(GeneratorError::InvalidConfig(format!(
+                        "Unknown package manager: {}",
+                        manager
+                    )));
+                }
+                names.values().collect()
             }
+        };
+        if let Some(name) = names.into_iter().find(|n| !is_package_name(n)) {
+            return Err(GeneratorError::InvalidConfig(format!(
+                "Invalid package name: {}",
+                name
+            )));
         }
-        "nodejs" => {
-            if is_alpine {
-                vec![
-                    format!("RUN {} && \\", update_cmd),
-                    format!("    {} {} nodejs npm && \\", package_manager, install_flag),
-                    "    rm -rf /var/cache/apk/*".to_string(),
-                ]
-            } else {
-                vec![
-                    format!("RUN {} && \\", update_cmd),
-                    format!("    {} {} curl && \\", package_manager, install_flag),
-                    format!(
-                        "    curl -fsSL https://deb.nodesource.com/setup_{}.x -o nodesource_setup.sh && \\",
-                        language.version
-                    ),
-                    "    bash nodesource_setup.sh && \\".to_string(),
-                    format!("    {} {} nodejs && \\", package_manager, install_flag),
-                    "    rm nodesource_setup.sh && \\".to_string(),
-                    "    rm -rf /var/lib/apt/lists/*".to_string(),
-                ]
-            }
+    }
+
+    let mut variables = config.env.keys().chain(config.args.keys());
+    if let Some(name) = variables.find(|n| !is_variable_name(n)) {
+        return Err(GeneratorError::InvalidConfig(format!(
+            "Invalid variable name: {}",
+            name
+        )));
+    }
+
+    if let Some(key) = config.labels.keys().find(|k| !is_label_key(k)) {
+        return Err(GeneratorError::InvalidConfig(format!(
+            "Invalid label key: {}",
+            key
+        )));
+    }
+
+    // Values are quoted, but a line break would still end the instruction
+    let mut values = config
+        .env
+        .values()
+        .chain(config.args.values().flatten())
+        .chain(config.labels.values());
+    if values.any(|v| v.chars().any(char::is_control)) {
+        return Err(GeneratorError::InvalidConfig(
+            "ENV, ARG and LABEL values cannot contain control characters".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Package names with optional version pins, e.g. `git`, `libstdc++`,
+/// `curl=7.88.1-10+deb12u8` (apt) or `nodejs~20` (apk)
+fn is_package_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphanumeric())
+        && name.chars().all(|c| {
+            c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-' | '_' | ':' | '~' | '=')
+        })
+}
+
+/// Names of the extra packages for `package_manager`
+fn resolve_packages(packages: &[SystemPackage], package_manager: PackageManager) -> Vec<String> {
+    packages
+        .iter()
+        .filter_map(|package| match package {
+            SystemPackage::Name(name) => Some(name.clone()),
+            SystemPackage::PerManager(names) => names.get(package_manager.name()).cloned(),
+        })
+        .collect()
+}
+
+/// LABEL and ENV instructions from the configuration
+fn get_metadata_section(config: &EnvironmentConfig) -> Option<Section> {
+    if config.labels.is_empty() && config.env.is_empty() {
+        return None;
+    }
+
+    let labels = config
+        .labels
+        .iter()
+        .map(|(key, value)| Instruction::Label(key.clone(), value.clone()));
+    let env = config
+        .env
+        .iter()
+        .map(|(key, value)| Instruction::Env(key.clone(), value.clone()));
+    Some(
+        Section::new()
+            .comment("Labels and environment")
+            .instructions(labels.chain(env)),
+    )
+}
+
+fn validate_ssh(ssh: &SshConfig, user: Option<&DevUser>) -> Result<(), GeneratorError> {
+    if ssh.port == 0 {
+        return Err(GeneratorError::InvalidConfig(
+            "SSH port must be between 1 and 65535".to_string(),
+        ));
+    }
+
+    if ssh.disable_password_auth && ssh.authorized_keys.is_empty() {
+        return Err(GeneratorError::InvalidConfig(
+            "Password authentication is disabled but no authorized keys are configured".to_string(),
+        ));
+    }
+
+    if ssh.hardened && user.is_none() {
+        return Err(GeneratorError::InvalidConfig(
+            "The hardened SSH profile needs a non-root login user".to_string(),
+        ));
+    }
+
+    if let Some(key) = ssh.authorized_keys.iter().find(|k| !is_public_key(k)) {
+        return Err(GeneratorError::InvalidConfig(format!(
+            "Invalid authorized key: {}",
+            key
+        )));
+    }
+
+    Ok(())
+}
+
+/// A single-line OpenSSH public key: key type, base64 blob and optional comment
+fn is_public_key(key: &str) -> bool {
+    const KEY_TYPES: &[&str] = &[
+        "ssh-ed25519",
+        "ssh-rsa",
+        "ecdsa-sha2-nistp256",
+        "ecdsa-sha2-nistp384",
+        "ecdsa-sha2-nistp521",
+        "sk-ssh-ed25519@openssh.com",
+        "sk-ecdsa-sha2-nistp256@openssh.com",
+    ];
+
+    let mut parts = key.split_whitespace();
+    let key_type = parts.next().unwrap_or_default();
+    let blob = parts.next().unwrap_or_default();
+
+    KEY_TYPES.contains(&key_type)
+        && !blob.is_empty()
+        && blob
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '='))
+        && !key
+            .chars()
+            .any(|c| matches!(c, '\'' | '<') || c.is_control())
+}
+
+/// Sections that build the sources in the builder stage and assemble the runtime stage
+fn get_runtime_stage(
+    multi_stage: &MultiStageConfig,
+    caches: Vec<CacheMount>,
+    metadata: Option<Section>,
+) -> Vec<Section> {
+    let mut build =
+        Section::new()
+            .comment("Build the application")
+            .instruction(Instruction::Copy {
+                from: None,
+                sources: vec![".".to_string()],
+                destination: ".".to_string(),
+            });
+    if let Some(build_command) = &multi_stage.build_command {
+        build = build.instruction(Instruction::Run {
+            mounts: caches,
+            commands: vec![build_command.clone()],
+        });
+    }
+
+    let runtime_image = match multi_stage.runtime {
+        RuntimeImage::DebianSlim => "debian:bookworm-slim",
+        RuntimeImage::Distroless => "gcr.io/distroless/cc-debian12",
+        RuntimeImage::Alpine => "alpine:3.22",
+    };
+    let from = Section::new()
+        .comment("Runtime stage with only the build artifacts")
+        .instruction(Instruction::From {
+            image: runtime_image.to_string(),
+            alias: Some("runtime".to_string()),
+        });
+
+    let mut runtime = Section::new().instruction(Instruction::Workdir("/app".to_string()));
+    for artifact in &multi_stage.artifacts {
+        let name = artifact
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or(artifact);
+        // COPY --from resolves paths from the builder's root, not its WORKDIR
+        let source = if artifact.starts_with('/') {
+            artifact.clone()
+        } else {
+            format!("/app/{}", artifact)
+        };
+        runtime = runtime.instruction(Instruction::Copy {
+            from: Some("builder".to_string()),
+            sources: vec![source],
+            destination: format!("/app/{}", name),
+        });
+    }
+
+    let mut sections = vec![build, from, runtime];
+    sections.extend(metadata);
+    if !multi_stage.command.is_empty() {
+        sections.push(Section::new().instruction(Instruction::Cmd(multi_stage.command.clone())));
+    }
+    sections
+}
+
+/// Create the (validated) dev user, replacing any account the base image
+/// already has under the same UID, e.g. `ubuntu` on Ubuntu 24.04
+fn get_user_setup_plan(user: &DevUser, target: &TargetOs) -> InstallPlan {
+    let package_manager = target.package_manager();
+    let alpine = package_manager == PackageManager::Apk;
+    let default_shell = if alpine { "/bin/sh" } else { "/bin/bash" };
+    let shell = user.shell.as_deref().unwrap_or(default_shell);
+
+    let mut packages = Vec::new();
+    if user.sudo {
+        packages.push("sudo");
+    }
+    // useradd is not part of the minimal openSUSE images
+    if package_manager == PackageManager::Zypper {
+        packages.push("shadow");
+    }
+    match shell.rsplit('/').next() {
+        Some("bash") if alpine => packages.push("bash"),
+        Some(extra @ ("zsh" | "fish")) => packages.push(extra),
+        _ => {}
+    }
+
+    let (name, uid, gid) = (&user.name, user.uid, user.gid);
+    let mut plan = InstallPlan::packages(&packages);
+    plan = if alpine {
+        plan.step(format!(
+            "if getent passwd {uid} >/dev/null; then deluser --remove-home \"$(getent passwd {uid} | cut -d: -f1)\"; fi"
+        ))
+        .step(format!(
+            "if ! getent group {gid} >/dev/null; then addgroup -g {gid} {name}; fi"
+        ))
+        .step(format!(
+            "adduser -D -u {uid} -G \"$(getent group {gid} | cut -d: -f1)\" -s {shell} {name}"
+        ))
+    } else {
+        plan.step(format!(
+            "if getent passwd {uid} >/dev/null; then userdel -r \"$(getent passwd {uid} | cut -d: -f1)\"; fi"
+        ))
+        .step(format!(
+            "if ! getent group {gid} >/dev/null; then groupadd --gid {gid} {name}; fi"
+        ))
+        .step(format!(
+            "useradd --uid {uid} --gid {gid} --create-home --shell {shell} {name}"
+        ))
+    };
+
+    if user.sudo {
+        plan = plan
+            .step(format!(
+                "echo '{name} ALL=(ALL) NOPASSWD:ALL' > /etc/sudoers.d/{name}"
+            ))
+            .step(format!("chmod 0440 /etc/sudoers.d/{name}"));
+    }
+
+    plan.step(format!("chown {uid}:{gid} /app"))
+}
+
+fn get_ssh_install_plan(
+    ssh: &SshConfig,
+    login_user: Option<&DevUser>,
+    target: &TargetOs,
+) -> InstallPlan {
+    let package_manager = target.package_manager();
+    let plan = match package_manager {
+        PackageManager::Apk | PackageManager::Pacman => InstallPlan::packages(&["openssh"]),
+        _ => InstallPlan::packages(&["openssh-server"]),
+    };
+
+    let mut plan = match package_manager {
+        // Debian-based packages generate host keys on install
+        PackageManager::Apt => plan.step("mkdir -p /var/run/sshd"),
+        // Recent openSUSE releases only ship the vendor config under /usr/etc
+        PackageManager::Zypper => plan
+            .step("[ -f /etc/ssh/sshd_config ] || cp /usr/etc/ssh/sshd_config /etc/ssh/sshd_config")
+            .step("ssh-keygen -A"),
+        _ => plan.step("ssh-keygen -A"),
+    };
+
+    // sshd keeps the first value it reads for a keyword, so the settings go
+    // on top of the file, ahead of the distro defaults and Include drop-ins
+    plan = plan
+        .step(format!(
+            "{{ {}; cat /etc/ssh/sshd_config; }} > /tmp/sshd_config",
+            print_lines(&sshd_settings(ssh, login_user))
+        ))
+        .step("mv /tmp/sshd_config /etc/ssh/sshd_config");
+
+    let home = login_user.map_or_else(|| "/root".to_string(), |u| format!("/home/{}", u.name));
+    if !ssh.authorized_keys.is_empty() {
+        let keys: Vec<String> = ssh
+            .authorized_keys
+            .iter()
+            .map(|key| key.trim().to_string())
+            .collect();
+        plan = plan
+            .step(format!("mkdir -p {}/.ssh", home))
+            .step(write_file(&format!("{}/.ssh/authorized_keys", home), &keys))
+            .step(format!(
+                "chmod 700 {0}/.ssh && chmod 600 {0}/.ssh/authorized_keys",
+                home
+            ));
+        if let Some(user) = login_user {
+            plan = plan.step(format!("chown -R {}:{} {}/.ssh", user.uid, user.gid, home));
         }
-        "rust" => {
-            let nightly_flag = if language.version == "nightly" {
-                "--default-toolchain nightly"
-            } else {
-                ""
-            };
-            let cleanup = if is_alpine {
-                "/var/cache/apk/*"
-            } else {
-                "/var/lib/apt/lists/*"
-            };
+    }
 
-            let build_tools = if is_alpine {
-                "curl gcc musl-dev"
-            } else {
-                "curl build-essential"
-            };
+    // New accounts are locked, which sshd without PAM (Alpine) treats as
+    // disabled even for key logins; `*` keeps them password-less but usable
+    if let Some(user) = login_user {
+        plan = plan.step(format!("echo '{}:*' | chpasswd -e", user.name));
+    }
 
-            let mut commands = vec![
-                format!("RUN {} && \\", update_cmd),
-                format!("    {} {} {} && \\", package_manager, install_flag, build_tools),
-                "    curl --proto '=https' --tlsv1.2 -sSf https://sh.rustup.rs -o rustup-init.sh && \\".to_string(),
-                format!("    sh rustup-init.sh -y {} && \\", nightly_flag),
-                "    rm rustup-init.sh && \\".to_string(),
-                format!("    rm -rf {}", cleanup),
-            ];
-
-            commands.push("ENV PATH=\"/root/.cargo/bin:${PATH}\"".to_string());
-            commands
+    plan.step("/usr/sbin/sshd -t")
+}
+
+/// sshd_config keywords for the chosen authentication methods and profile
+fn sshd_settings(ssh: &SshConfig, login_user: Option<&DevUser>) -> Vec<String> {
+    let password_auth = !ssh.disable_password_auth;
+    let permit_root_login = match login_user {
+        Some(_) => "no",
+        None if password_auth => "yes",
+        None => "prohibit-password",
+    };
+
+    let yes_no = |enabled: bool| if enabled { "yes" } else { "no" };
+    let mut settings = vec![
+        format!("Port {}", ssh.port),
+        format!("PermitRootLogin {}", permit_root_login),
+        format!("PasswordAuthentication {}", yes_no(password_auth)),
+        "KbdInteractiveAuthentication no".to_string(),
+        "PubkeyAuthentication yes".to_string(),
+        "PermitEmptyPasswords no".to_string(),
+    ];
+
+    if ssh.hardened {
+        // TCP forwarding stays on, remote IDEs tunnel through it
+        if let Some(user) = login_user {
+            settings.push(format!("AllowUsers {}", user.name));
         }
-        _ => vec![],
+        settings.extend(
+            [
+                "MaxAuthTries 3",
+                "LoginGraceTime 30",
+                "X11Forwarding no",
+                "AllowAgentForwarding no",
+                "ClientAliveInterval 300",
+                "ClientAliveCountMax 2",
+            ]
+            .map(String::from),
+        );
     }
+
+    settings
+}
+
+/// Entrypoint that sets the login user's password from the environment at
+/// runtime, so it never ends up in an image layer
+fn get_password_entrypoint(login_user: Option<&DevUser>) -> Vec<Section> {
+    let (name, variable) = match login_user {
+        Some(user) => (user.name.as_str(), "SSH_PASSWORD"),
+        None => ("root", "ROOT_PASSWORD"),
+    };
+
+    let script = [
+        "#!/bin/sh".to_string(),
+        format!("if [ -n \"${}\" ]; then", variable),
+        format!("  echo \"{}:${}\" | chpasswd", name, variable),
+        "fi".to_string(),
+        "exec \"$@\"".to_string(),
+    ];
+
+    vec![
+        Section::new()
+            .comment("Create entrypoint script to set password securely")
+            .instruction(Instruction::run(vec![
+                write_file("/entrypoint.sh", &script),
+                "chmod +x /entrypoint.sh".to_string(),
+            ])),
+        Section::new().instruction(Instruction::Entrypoint(vec!["/entrypoint.sh".to_string()])),
+    ]
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::config::{LanguageOptions, OsConfig};
 
     #[test]
     fn test_generate_dockerfile_ubuntu_python() {
@@ -210,11 +765,13 @@ mod tests {
             languages: vec![Language {
                 name: "python".to_string(),
                 version: "3.11".to_string(),
+                options: LanguageOptions::default(),
             }],
             ssh: None,
+            ..Default::default()
         };
 
-        let dockerfile = generate_dockerfile(&config);
+        let dockerfile = generate_dockerfile(&config).unwrap();
         assert!(dockerfile.contains("FROM ubuntu:22.04"));
         assert!(dockerfile.contains("python3.11"));
         assert!(dockerfile.contains("python3.11-pip"));
@@ -227,19 +784,21 @@ mod tests {
             name: None,
             os: OsConfig {
                 os_type: "alpine".to_string(),
-                version: "latest".to_string(),
+                version: "3.20".to_string(),
             },
             languages: vec![Language {
                 name: "nodejs".to_string(),
                 version: "20".to_string(),
+                options: LanguageOptions::default(),
             }],
             ssh: None,
+            ..Default::default()
         };
 
-        let dockerfile = generate_dockerfile(&config);
-        assert!(dockerfile.contains("FROM alpine:latest"));
+        let dockerfile = generate_dockerfile(&config).unwrap();
+        assert!(dockerfile.contains("FROM alpine:3.20"));
         assert!(dockerfile.contains("apk"));
-        assert!(dockerfile.contains("nodejs npm"));
+        assert!(dockerfile.contains("nodejs~20 npm"));
     }
 
     #[test]
@@ -254,18 +813,1021 @@ mod tests {
                 Language {
                     name: "python".to_string(),
                     version: "3.11".to_string(),
+                    options: LanguageOptions::default(),
                 },
                 Language {
                     name: "rust".to_string(),
                     version: "stable".to_string(),
+                    options: LanguageOptions::default(),
                 },
             ],
             ssh: None,
+            ..Default::default()
         };
 
-        let dockerfile = generate_dockerfile(&config);
+        let dockerfile = generate_dockerfile(&config).unwrap();
         assert!(dockerfile.contains("FROM debian:bookworm"));
         assert!(dockerfile.contains("python"));
         assert!(dockerfile.contains("rustup"));
     }
+
+    #[test]
+    fn test_generate_dockerfile_unknown_language() {
+        let config = EnvironmentConfig {
+            name: None,
+            os: OsConfig {
+                os_type: "ubuntu".to_string(),
+                version: "22.04".to_string(),
+            },
+            languages: vec![Language {
+                name: "cobol".to_string(),
+                version: "85".to_string(),
+                options: LanguageOptions::default(),
+            }],
+            ssh: None,
+            ..Default::default()
+        };
+
+        let err = generate_dockerfile(&config).unwrap_err();
+        assert!(matches!(err, GeneratorError::UnsupportedLanguage(name) if name == "cobol"));
+    }
+
+    #[test]
+    fn test_generate_dockerfile_unknown_os() {
+        let config = EnvironmentConfig {
+            name: None,
+            os: OsConfig {
+                os_type: "plan9".to_string(),
+                version: "4".to_string(),
+            },
+            languages: vec![Language {
+                name: "python".to_string(),
+                version: "3.11".to_string(),
+                options: LanguageOptions::default(),
+            }],
+            ssh: None,
+            ..Default::default()
+        };
+
+        let err = generate_dockerfile(&config).unwrap_err();
+        assert!(matches!(err, GeneratorError::UnsupportedOs(os) if os == "plan9"));
+    }
+
+    #[test]
+    fn test_generate_dockerfile_merges_system_packages() {
+        let language = |name: &str, version: &str| Language {
+            name: name.to_string(),
+            version: version.to_string(),
+            options: LanguageOptions::default(),
+        };
+        let config = EnvironmentConfig {
+            name: None,
+            os: OsConfig {
+                os_type: "ubuntu".to_string(),
+                version: "22.04".to_string(),
+            },
+            languages: vec![
+                language("go", "1.22.5"),
+                language("nodejs", "20"),
+                language("python", "3.12"),
+            ],
+            ..Default::default()
+        };
+
+        let dockerfile = generate_dockerfile(&config).unwrap();
+        assert!(dockerfile.contains(
+            "# Install system packages\nRUN apt-get update && \\\n    \
+             DEBIAN_FRONTEND=noninteractive apt-get install -y --no-install-recommends \
+             ca-certificates curl tar gzip software-properties-common && \\\n    \
+             rm -rf /var/lib/apt/lists/*\n"
+        ));
+        // Only steps that add a repository refresh the index again, and clean up after it
+        assert_eq!(dockerfile.matches("apt-get update").count(), 2);
+        assert_eq!(dockerfile.matches("rm -rf /var/lib/apt/lists/*").count(), 3);
+        assert!(
+            dockerfile.find("# Install system packages").unwrap()
+                < dockerfile.find("# Install go 1.22.5").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_generate_dockerfile_fedora_with_ssh() {
+        let config = EnvironmentConfig {
+            name: None,
+            os: OsConfig {
+                os_type: "fedora".to_string(),
+                version: "40".to_string(),
+            },
+            languages: vec![Language {
+                name: "rust".to_string(),
+                version: "stable".to_string(),
+                options: LanguageOptions::default(),
+            }],
+            ssh: Some(SshConfig {
+                enabled: true,
+                port: 22,
+                password: String::new(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let dockerfile = generate_dockerfile(&config).unwrap();
+        assert!(dockerfile.contains("FROM fedora:40"));
+        // The SSH server is installed in the same layer as the toolchain packages
+        assert!(dockerfile
+            .contains("dnf install -y ca-certificates curl gcc gcc-c++ make openssh-server"));
+        assert_eq!(dockerfile.matches("dnf makecache").count(), 1);
+        assert!(dockerfile.contains("ssh-keygen -A"));
+        assert!(dockerfile.contains("dnf clean all"));
+        assert!(!dockerfile.contains("apt-get"));
+    }
+
+    #[test]
+    fn test_generate_dockerfile_arch_and_opensuse_ssh_packages() {
+        for (os_type, version, expected) in [
+            (
+                "arch",
+                "latest",
+                "pacman -S --noconfirm --needed nodejs-lts-iron npm openssh",
+            ),
+            (
+                "opensuse",
+                "15.6",
+                "zypper --non-interactive install nodejs20 npm20 openssh-server",
+            ),
+        ] {
+            let config = EnvironmentConfig {
+                name: None,
+                os: OsConfig {
+                    os_type: os_type.to_string(),
+                    version: version.to_string(),
+                },
+                languages: vec![Language {
+                    name: "nodejs".to_string(),
+                    version: "20".to_string(),
+                    options: LanguageOptions::default(),
+                }],
+                ssh: Some(SshConfig {
+                    enabled: true,
+                    port: 22,
+                    password: String::new(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+
+            let dockerfile = generate_dockerfile(&config).unwrap();
+            assert!(dockerfile.contains(expected), "{}", dockerfile);
+        }
+    }
+
+    #[test]
+    fn test_generate_dockerfile_alpine_unavailable_python() {
+        let config = EnvironmentConfig {
+            name: None,
+            os: OsConfig {
+                os_type: "alpine".to_string(),
+                version: "3.19".to_string(),
+            },
+            languages: vec![Language {
+                name: "python".to_string(),
+                version: "3.12".to_string(),
+                options: LanguageOptions::default(),
+            }],
+            ssh: None,
+            ..Default::default()
+        };
+
+        let err = generate_dockerfile(&config).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "python 3.12 is not available on alpine 3.19"
+        );
+    }
+
+    #[test]
+    fn test_generate_dockerfile_alpine_nodejs_versions() {
+        // The release package pinned to the major where Alpine ships it, the
+        // verified musl build otherwise
+        for (alpine, node, packages, musl_build) in [
+            ("3.20", "20", "nodejs~20 npm", false),
+            ("3.20", "22", "nodejs-current~22 npm", false),
+            ("3.22", "18", "curl libstdc++ xz", true),
+            ("3.22", "22", "nodejs~22 npm", false),
+        ] {
+            let config = EnvironmentConfig {
+                name: None,
+                os: OsConfig {
+                    os_type: "alpine".to_string(),
+                    version: alpine.to_string(),
+                },
+                languages: vec![Language {
+                    name: "nodejs".to_string(),
+                    version: node.to_string(),
+                    options: LanguageOptions::default(),
+                }],
+                ssh: None,
+                ..Default::default()
+            };
+
+            let dockerfile = generate_dockerfile(&config).unwrap();
+            let install = format!(
+                "RUN apk update && \\\n    apk add {} && \\\n    rm -rf /var/cache/apk/*\n",
+                packages
+            );
+            assert!(dockerfile.contains(&install), "{}", dockerfile);
+            assert_eq!(
+                dockerfile.contains(&format!("# Install nodejs {}\n", node)),
+                musl_build,
+                "{}",
+                dockerfile
+            );
+            assert_eq!(
+                dockerfile.contains(&format!("node --version | grep -q '^v{}\\.'", node)),
+                musl_build,
+                "{}",
+                dockerfile
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_dockerfile_debian_python_matrix() {
+        for (debian, python, distro_python) in [
+            ("bullseye", "3.9", true),
+            ("bullseye", "3.12", false),
+            ("bookworm", "3.11", true),
+            ("bookworm", "3.10", false),
+            ("bookworm", "3.12", false),
+        ] {
+            let config = EnvironmentConfig {
+                name: None,
+                os: OsConfig {
+                    os_type: "debian".to_string(),
+                    version: debian.to_string(),
+                },
+                languages: vec![Language {
+                    name: "python".to_string(),
+                    version: python.to_string(),
+                    options: LanguageOptions::default(),
+                }],
+                ssh: None,
+                ..Default::default()
+            };
+
+            let dockerfile = generate_dockerfile(&config).unwrap();
+            assert!(dockerfile.contains(&format!("FROM debian:{}", debian)));
+            assert!(!dockerfile.contains("deadsnakes"), "{}", dockerfile);
+            assert_eq!(
+                dockerfile.contains(&format!("uv python install {}", python)),
+                !distro_python,
+                "{}",
+                dockerfile
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_dockerfile_go() {
+        let config = EnvironmentConfig {
+            name: None,
+            os: OsConfig {
+                os_type: "ubuntu".to_string(),
+                version: "24.04".to_string(),
+            },
+            languages: vec![Language {
+                name: "go".to_string(),
+                version: "1.22.5".to_string(),
+                options: LanguageOptions::default(),
+            }],
+            ssh: None,
+            ..Default::default()
+        };
+
+        let dockerfile = generate_dockerfile(&config).unwrap();
+        assert!(dockerfile.contains("# Install go 1.22.5"));
+        assert!(dockerfile.contains("sha256sum -c -"));
+        assert!(dockerfile.contains("ENV GOPATH=\"/go\""));
+    }
+
+    #[test]
+    fn test_generate_dockerfile_java_with_maven() {
+        let config = EnvironmentConfig {
+            name: None,
+            os: OsConfig {
+                os_type: "rocky".to_string(),
+                version: "9".to_string(),
+            },
+            languages: vec![Language {
+                name: "java".to_string(),
+                version: "21".to_string(),
+                options: LanguageOptions {
+                    distribution: Some("openjdk".to_string()),
+                    tools: vec!["maven".to_string()],
+                    ..Default::default()
+                },
+            }],
+            ssh: None,
+            ..Default::default()
+        };
+
+        let dockerfile = generate_dockerfile(&config).unwrap();
+        assert!(dockerfile
+            .contains("dnf install -y java-21-openjdk-devel ca-certificates curl tar gzip"));
+        assert!(dockerfile.contains("ENV JAVA_HOME=\"/opt/java\""));
+        assert!(dockerfile.contains("ENV PATH=\"/opt/maven/bin:${PATH}\""));
+    }
+
+    #[test]
+    fn test_generate_dockerfile_scripting_runtimes_on_alpine() {
+        let config = EnvironmentConfig {
+            name: None,
+            os: OsConfig {
+                os_type: "alpine".to_string(),
+                version: "3.21".to_string(),
+            },
+            languages: ["ruby:3.3", "php:8.4", "perl:5.40"]
+                .iter()
+                .map(|spec| {
+                    let (name, version) = spec.split_once(':').unwrap();
+                    Language {
+                        name: name.to_string(),
+                        version: version.to_string(),
+                        options: LanguageOptions::default(),
+                    }
+                })
+                .collect(),
+            ssh: None,
+            ..Default::default()
+        };
+
+        let dockerfile = generate_dockerfile(&config).unwrap();
+        assert!(dockerfile.contains("ruby~3.3 ruby-dev ruby-bundler"));
+        assert!(dockerfile.contains("php84 php84-curl"));
+        assert!(dockerfile.contains("perl~5.40 perl-dev perl-app-cpanminus"));
+        assert!(dockerfile.contains("--filename=composer"));
+    }
+
+    fn multi_stage_config(runtime: RuntimeImage) -> EnvironmentConfig {
+        EnvironmentConfig {
+            name: None,
+            os: OsConfig {
+                os_type: "debian".to_string(),
+                version: "bookworm".to_string(),
+            },
+            languages: vec![Language {
+                name: "rust".to_string(),
+                version: "1.83.0".to_string(),
+                options: LanguageOptions::default(),
+            }],
+            ssh: None,
+            user: None,
+            multi_stage: Some(MultiStageConfig {
+                runtime,
+                build_command: Some("cargo build --release".to_string()),
+                artifacts: vec!["target/release/server".to_string()],
+                command: vec!["/app/server".to_string()],
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// `base` with the top-level fields of `extras` added or replaced
+    fn extras_config(mut base: serde_json::Value, extras: serde_json::Value) -> EnvironmentConfig {
+        base.as_object_mut()
+            .unwrap()
+            .extend(extras.as_object().unwrap().clone());
+        serde_json::from_value(base).unwrap()
+    }
+
+    fn go_config() -> serde_json::Value {
+        serde_json::json!({
+            "os": { "os_type": "ubuntu", "version": "24.04" },
+            "languages": [{ "name": "go", "version": "1.22.5" }]
+        })
+    }
+
+    #[test]
+    fn test_generate_dockerfile_extras() {
+        let config = extras_config(
+            go_config(),
+            serde_json::json!({
+                "packages": ["git", "jq", { "apt": "xz-utils", "apk": "xz" }, { "dnf": "openssl-devel" }],
+                "env": { "EDITOR": "vim" },
+                "args": { "APP_VERSION": "1.0", "TOKEN": null },
+                "labels": { "org.opencontainers.image.title": "dev" }
+            }),
+        );
+        let dockerfile = generate_dockerfile(&config).unwrap();
+        assert!(dockerfile.contains(
+            "apt-get install -y --no-install-recommends ca-certificates curl tar gzip git jq xz-utils && \\"
+        ));
+        assert!(!dockerfile.contains("openssl-devel"));
+        assert!(dockerfile.contains("# Build arguments\nARG APP_VERSION=\"1.0\"\nARG TOKEN\n"));
+        assert!(dockerfile.contains(
+            "# Labels and environment\nLABEL org.opencontainers.image.title=\"dev\"\nENV EDITOR=\"vim\""
+        ));
+        assert!(dockerfile.find("ENV EDITOR").unwrap() > dockerfile.find("ENV GOPATH").unwrap());
+
+        // Labels and environment go to the runtime stage of multi-stage builds
+        let config = EnvironmentConfig {
+            env: config.env,
+            ..multi_stage_config(RuntimeImage::Alpine)
+        };
+        let dockerfile = generate_dockerfile(&config).unwrap();
+        assert!(dockerfile.find("ENV EDITOR").unwrap() > dockerfile.find("AS runtime").unwrap());
+    }
+
+    #[test]
+    fn test_generate_dockerfile_rejects_unsafe_extras() {
+        for (extras, message) in [
+            (
+                serde_json::json!({ "packages": ["git; rm -rf /"] }),
+                "Invalid package name: git; rm -rf /",
+            ),
+            (
+                serde_json::json!({ "packages": ["--allow-unauthenticated"] }),
+                "Invalid package name: --allow-unauthenticated",
+            ),
+            (
+                serde_json::json!({ "packages": [{ "brew": "git" }] }),
+                "Unknown package manager: brew",
+            ),
+            (
+                serde_json::json!({ "packages": [{ "apt": "$(id)" }] }),
+                "Invalid package name: $(id)",
+            ),
+            (
+                serde_json::json!({ "args": { "A B": null } }),
+                "Invalid variable name: A B",
+            ),
+            (
+                serde_json::json!({ "labels": { "a\"b": "x" } }),
+                "Invalid label key: a\"b",
+            ),
+            (
+                serde_json::json!({ "env": { "EDITOR": "vim\nRUN id" } }),
+                "ENV, ARG and LABEL values cannot contain control characters",
+            ),
+        ] {
+            let err = generate_dockerfile(&extras_config(go_config(), extras)).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("Invalid configuration: {}", message)
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_dockerfile_build_cache() {
+        let config = EnvironmentConfig {
+            build_cache: true,
+            ..multi_stage_config(RuntimeImage::DebianSlim)
+        };
+        let dockerfile = generate_dockerfile(&config).unwrap();
+        assert!(dockerfile.starts_with("FROM debian:bookworm"));
+        assert!(dockerfile.contains("rm -f /etc/apt/apt.conf.d/docker-clean"));
+        assert!(dockerfile.contains(
+            "RUN --mount=type=cache,target=/var/cache/apt,sharing=locked \\\n    \
+             --mount=type=cache,target=/var/lib/apt,sharing=locked \\\n    apt-get update"
+        ));
+        // The caches stay in the mounts instead of being removed
+        assert!(!dockerfile.contains("rm -rf /var/lib/apt/lists/*"));
+        assert!(dockerfile.contains(
+            "--mount=type=cache,target=/usr/local/cargo/git \\\n    cargo build --release"
+        ));
+
+        let dockerfile =
+            generate_dockerfile(&multi_stage_config(RuntimeImage::DebianSlim)).unwrap();
+        assert!(!dockerfile.contains("--mount"));
+        assert!(!dockerfile.contains("syntax"));
+    }
+
+    #[test]
+    fn test_generate_dockerfile_build_cache_language_installs() {
+        let config = EnvironmentConfig {
+            os: OsConfig {
+                os_type: "debian".to_string(),
+                version: "bookworm".to_string(),
+            },
+            languages: vec![
+                Language {
+                    name: "python".to_string(),
+                    version: "3.12".to_string(),
+                    options: LanguageOptions::default(),
+                },
+                Language {
+                    name: "nodejs".to_string(),
+                    version: "20".to_string(),
+                    options: LanguageOptions::default(),
+                },
+            ],
+            build_cache: true,
+            ..Default::default()
+        };
+        let dockerfile = generate_dockerfile(&config).unwrap();
+        let python = dockerfile
+            .split("# Install python 3.12\n")
+            .nth(1)
+            .unwrap()
+            .split("\n\n")
+            .next()
+            .unwrap();
+        assert!(
+            python.starts_with("RUN --mount=type=cache,target=/root/.cache/pip \\\n"),
+            "{}",
+            dockerfile
+        );
+        let nodejs = dockerfile.split("# Install nodejs 20\n").nth(1).unwrap();
+        assert!(
+            nodejs.contains("--mount=type=cache,target=/root/.npm \\\n"),
+            "{}",
+            dockerfile
+        );
+        assert!(!nodejs.contains("/root/.cache/pip"));
+    }
+
+    #[test]
+    fn test_generate_dockerfile_multi_stage() {
+        let dockerfile =
+            generate_dockerfile(&multi_stage_config(RuntimeImage::DebianSlim)).unwrap();
+        assert!(dockerfile.starts_with("FROM debian:bookworm AS builder"));
+        assert!(dockerfile.contains("rustup-init.sh"));
+        assert!(dockerfile.contains("COPY . .\nRUN cargo build --release"));
+        assert!(dockerfile.contains("FROM debian:bookworm-slim AS runtime"));
+        assert!(dockerfile.contains("COPY --from=builder /app/target/release/server /app/server"));
+        assert!(dockerfile.ends_with("CMD [\"/app/server\"]"));
+
+        // Toolchains stay in the builder stage
+        let runtime = dockerfile.split("AS runtime").nth(1).unwrap();
+        assert!(!runtime.contains("RUN "));
+    }
+
+    #[test]
+    fn test_generate_dockerfile_multi_stage_distroless() {
+        let dockerfile =
+            generate_dockerfile(&multi_stage_config(RuntimeImage::Distroless)).unwrap();
+        assert!(dockerfile.contains("FROM gcr.io/distroless/cc-debian12 AS runtime"));
+    }
+
+    #[test]
+    fn test_generate_dockerfile_multi_stage_validation() {
+        let mut config = multi_stage_config(RuntimeImage::Alpine);
+        config.ssh = Some(SshConfig {
+            enabled: true,
+            port: 22,
+            password: String::new(),
+            ..Default::default()
+        });
+        assert!(matches!(
+            generate_dockerfile(&config),
+            Err(GeneratorError::InvalidConfig(_))
+        ));
+
+        let mut config = multi_stage_config(RuntimeImage::Alpine);
+        config.multi_stage.as_mut().unwrap().artifacts.clear();
+        assert!(matches!(
+            generate_dockerfile(&config),
+            Err(GeneratorError::InvalidConfig(_))
+        ));
+
+        let mut config = multi_stage_config(RuntimeImage::Alpine);
+        config.multi_stage.as_mut().unwrap().artifacts = vec!["--chmod=777".to_string()];
+        let err = generate_dockerfile(&config).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid configuration: Invalid artifact path: --chmod=777"
+        );
+    }
+
+    #[test]
+    fn test_generate_dockerfile_multi_stage_absolute_artifact() {
+        let mut config = multi_stage_config(RuntimeImage::DebianSlim);
+        config.multi_stage.as_mut().unwrap().artifacts =
+            vec!["/usr/local/cargo/bin/server".to_string()];
+        let dockerfile = generate_dockerfile(&config).unwrap();
+        assert!(dockerfile.contains("COPY --from=builder /usr/local/cargo/bin/server /app/server"));
+    }
+
+    fn user_config(os_type: &str, version: &str, user: DevUser) -> EnvironmentConfig {
+        EnvironmentConfig {
+            name: None,
+            os: OsConfig {
+                os_type: os_type.to_string(),
+                version: version.to_string(),
+            },
+            languages: vec![Language {
+                name: "go".to_string(),
+                version: "1.22.5".to_string(),
+                options: LanguageOptions::default(),
+            }],
+            ssh: None,
+            user: Some(user),
+            ..Default::default()
+        }
+    }
+
+    fn dev_user(sudo: bool, shell: Option<&str>) -> DevUser {
+        DevUser {
+            name: "dev".to_string(),
+            uid: 1000,
+            gid: 1000,
+            sudo,
+            shell: shell.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_generate_dockerfile_dev_user() {
+        let dockerfile =
+            generate_dockerfile(&user_config("ubuntu", "24.04", dev_user(true, None))).unwrap();
+        assert!(dockerfile.contains("# Create development user dev"));
+        assert!(dockerfile.contains(
+            "apt-get install -y --no-install-recommends ca-certificates curl tar gzip sudo"
+        ));
+        assert!(dockerfile.contains("userdel -r \"$(getent passwd 1000 | cut -d: -f1)\""));
+        assert!(dockerfile
+            .contains("useradd --uid 1000 --gid 1000 --create-home --shell /bin/bash dev"));
+        assert!(dockerfile.contains("echo 'dev ALL=(ALL) NOPASSWD:ALL' > /etc/sudoers.d/dev"));
+        assert!(dockerfile.contains("chown 1000:1000 /app"));
+        assert!(dockerfile.contains("USER dev\n\n# Set default command"));
+        // The user is created after the toolchains are installed as root
+        assert!(dockerfile.find("go.tar.gz").unwrap() < dockerfile.find("useradd").unwrap());
+    }
+
+    #[test]
+    fn test_generate_dockerfile_dev_user_alpine() {
+        let user = DevUser {
+            uid: 501,
+            gid: 20,
+            ..dev_user(false, Some("/bin/bash"))
+        };
+        let dockerfile = generate_dockerfile(&user_config("alpine", "3.21", user)).unwrap();
+        assert!(dockerfile.contains("apk add ca-certificates curl tar gzip bash"));
+        assert!(dockerfile.contains("if ! getent group 20 >/dev/null; then addgroup -g 20 dev; fi"));
+        assert!(dockerfile.contains("adduser -D -u 501 -G"));
+        assert!(!dockerfile.contains("sudo"));
+        assert!(dockerfile.contains("chown 501:20 /app"));
+    }
+
+    #[test]
+    fn test_generate_dockerfile_dev_user_without_packages() {
+        let dockerfile =
+            generate_dockerfile(&user_config("debian", "bookworm", dev_user(false, None))).unwrap();
+        let user_layer = dockerfile
+            .split("# Create development user dev\n")
+            .nth(1)
+            .unwrap();
+        assert!(user_layer.starts_with("RUN if getent passwd 1000"));
+    }
+
+    #[test]
+    fn test_generate_dockerfile_dev_user_keeps_root_for_ssh() {
+        let mut config = user_config("debian", "bookworm", dev_user(false, None));
+        config.ssh = Some(SshConfig {
+            enabled: true,
+            port: 22,
+            password: String::new(),
+            ..Default::default()
+        });
+        let dockerfile = generate_dockerfile(&config).unwrap();
+        assert!(dockerfile.contains("useradd"));
+        assert!(!dockerfile.contains("USER dev"));
+    }
+
+    #[test]
+    fn test_generate_dockerfile_dev_user_validation() {
+        for user in [
+            DevUser {
+                name: "root".to_string(),
+                ..dev_user(false, None)
+            },
+            DevUser {
+                name: "Dev User".to_string(),
+                ..dev_user(false, None)
+            },
+            DevUser {
+                uid: 0,
+                ..dev_user(false, None)
+            },
+            dev_user(false, Some("bash; rm -rf /")),
+        ] {
+            assert!(matches!(
+                generate_dockerfile(&user_config("ubuntu", "22.04", user)),
+                Err(GeneratorError::InvalidConfig(_))
+            ));
+        }
+
+        let mut config = multi_stage_config(RuntimeImage::DebianSlim);
+        config.user = Some(dev_user(false, None));
+        assert!(matches!(
+            generate_dockerfile(&config),
+            Err(GeneratorError::InvalidConfig(_))
+        ));
+    }
+
+    const KEY: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIGxA3ZsPq0t7tJvHdxw0yXqUrkUl0vmQ1HhBhwGlC4B7 dev@laptop";
+
+    fn ssh_config(os_type: &str, version: &str, ssh: SshConfig) -> EnvironmentConfig {
+        EnvironmentConfig {
+            name: None,
+            os: OsConfig {
+                os_type: os_type.to_string(),
+                version: version.to_string(),
+            },
+            languages: Vec::new(),
+            ssh: Some(SshConfig {
+                enabled: true,
+                port: 22,
+                ..ssh
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// The settings prepended to sshd_config
+    fn sshd_settings_of(dockerfile: &str) -> Vec<String> {
+        let line = dockerfile
+            .lines()
+            .find(|line| line.contains("cat /etc/ssh/sshd_config"))
+            .unwrap();
+        line.split('\'')
+            .skip(3)
+            .step_by(2)
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_ssh_password_login_as_root() {
+        let dockerfile =
+            generate_dockerfile(&ssh_config("debian", "bookworm", SshConfig::default())).unwrap();
+        let settings = sshd_settings_of(&dockerfile);
+        assert!(settings.contains(&"PermitRootLogin yes".to_string()));
+        assert!(settings.contains(&"PasswordAuthentication yes".to_string()));
+        assert!(dockerfile.contains("echo \"root:$ROOT_PASSWORD\" | chpasswd"));
+        assert!(dockerfile.contains("ENTRYPOINT [\"/entrypoint.sh\"]"));
+        assert!(dockerfile.contains("/usr/sbin/sshd -t"));
+    }
+
+    #[test]
+    fn test_ssh_key_only_root_login() {
+        let ssh = SshConfig {
+            authorized_keys: vec![KEY.to_string()],
+            disable_password_auth: true,
+            ..Default::default()
+        };
+        let dockerfile = generate_dockerfile(&ssh_config("alpine", "3.21", ssh)).unwrap();
+        let settings = sshd_settings_of(&dockerfile);
+        assert_eq!(
+            &settings[..4],
+            [
+                "Port 22",
+                "PermitRootLogin prohibit-password",
+                "PasswordAuthentication no",
+                "KbdInteractiveAuthentication no",
+            ]
+        );
+        assert!(dockerfile.contains(&format!(
+            "printf '%s\\n' '{}' > /root/.ssh/authorized_keys",
+            KEY
+        )));
+        assert!(dockerfile.contains("chmod 700 /root/.ssh && chmod 600 /root/.ssh/authorized_keys"));
+        assert!(!dockerfile.contains("entrypoint.sh"));
+    }
+
+    #[test]
+    fn test_ssh_hardened_login_user() {
+        let mut config = ssh_config(
+            "ubuntu",
+            "24.04",
+            SshConfig {
+                authorized_keys: vec![KEY.to_string()],
+                disable_password_auth: true,
+                hardened: true,
+                ..Default::default()
+            },
+        );
+        config.user = Some(dev_user(false, None));
+
+        let dockerfile = generate_dockerfile(&config).unwrap();
+        let settings = sshd_settings_of(&dockerfile);
+        for expected in [
+            "PermitRootLogin no",
+            "PasswordAuthentication no",
+            "PubkeyAuthentication yes",
+            "PermitEmptyPasswords no",
+            "AllowUsers dev",
+            "MaxAuthTries 3",
+            "X11Forwarding no",
+            "AllowAgentForwarding no",
+            "ClientAliveInterval 300",
+        ] {
+            assert!(settings.contains(&expected.to_string()), "{:?}", settings);
+        }
+        assert!(dockerfile.contains("> /home/dev/.ssh/authorized_keys"));
+        assert!(dockerfile.contains("chown -R 1000:1000 /home/dev/.ssh"));
+        assert!(dockerfile.contains("echo 'dev:*' | chpasswd -e"));
+        // The user exists before sshd is configured for it
+        assert!(dockerfile.find("useradd").unwrap() < dockerfile.find("AllowUsers").unwrap());
+    }
+
+    #[test]
+    fn test_ssh_login_user_password() {
+        let mut config = ssh_config("fedora", "40", SshConfig::default());
+        config.user = Some(dev_user(false, None));
+
+        let dockerfile = generate_dockerfile(&config).unwrap();
+        assert!(sshd_settings_of(&dockerfile).contains(&"PermitRootLogin no".to_string()));
+        assert!(dockerfile.contains("echo \"dev:$SSH_PASSWORD\" | chpasswd"));
+        assert!(!dockerfile.contains("ROOT_PASSWORD"));
+    }
+
+    #[test]
+    fn test_ssh_validation() {
+        let invalid = [
+            // No way to log in
+            SshConfig {
+                disable_password_auth: true,
+                ..Default::default()
+            },
+            // Hardened profile without a login user
+            SshConfig {
+                authorized_keys: vec![KEY.to_string()],
+                hardened: true,
+                ..Default::default()
+            },
+            SshConfig {
+                authorized_keys: vec!["ssh-ed25519 AAAA' && rm -rf / #".to_string()],
+                ..Default::default()
+            },
+            SshConfig {
+                authorized_keys: vec!["not a key".to_string()],
+                ..Default::default()
+            },
+        ];
+        for ssh in invalid {
+            assert!(matches!(
+                generate_dockerfile(&ssh_config("debian", "bookworm", ssh)),
+                Err(GeneratorError::InvalidConfig(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_ssh_custom_port() {
+        let mut config = ssh_config("debian", "bookworm", SshConfig::default());
+        config.ssh.as_mut().unwrap().port = 2222;
+
+        let dockerfile = generate_dockerfile(&config).unwrap();
+        assert_eq!(sshd_settings_of(&dockerfile)[0], "Port 2222");
+        assert!(dockerfile.contains("EXPOSE 2222\nLABEL containerhelper.ssh.port=\"2222\""));
+        assert!(!dockerfile.contains("EXPOSE 22\n"));
+
+        config.ssh.as_mut().unwrap().port = 0;
+        assert!(matches!(
+            generate_dockerfile(&config),
+            Err(GeneratorError::InvalidConfig(_))
+        ));
+    }
+
+    mod properties {
+        use super::*;
+        use crate::services::dockerfile_parser::{import_dockerfile, parse};
+        use proptest::prelude::*;
+
+        const LANGUAGES: &[&str] = &[
+            "python", "nodejs", "rust", "go", "java", "ruby", "php", "perl",
+        ];
+
+        /// Strings wrapped around shell or Dockerfile metacharacters
+        fn hostile() -> impl Strategy<Value = String> {
+            let payloads = prop::sample::select(vec![
+                "; id",
+                "$(id)",
+                "`id`",
+                "'",
+                "\"",
+                "\n",
+                "\nRUN id",
+                " && id",
+                "| sh",
+                "\\",
+                "${PATH}",
+                "#",
+                " ",
+                "\r",
+                "<<EOF",
+                ">/etc/passwd",
+                "\t",
+                "*",
+            ]);
+            ("[a-z0-9.]{0,6}", payloads, "[a-z0-9.]{0,6}")
+                .prop_map(|(before, payload, after)| format!("{}{}{}", before, payload, after))
+        }
+
+        fn dev_config() -> serde_json::Value {
+            serde_json::json!({
+                "os": { "os_type": "debian", "version": "bookworm" },
+                "languages": [{ "name": "go", "version": "1.22.5" }],
+                "user": { "name": "dev" }
+            })
+        }
+
+        fn keywords(dockerfile: &str) -> Vec<String> {
+            parse(dockerfile)
+                .unwrap()
+                .into_iter()
+                .map(|i| i.keyword)
+                .collect()
+        }
+
+        proptest! {
+            #[test]
+            fn hostile_language_values_are_rejected(
+                value in hostile(),
+                language in prop::sample::select(LANGUAGES),
+                field in 0..6usize,
+            ) {
+                let mut options = serde_json::json!({});
+                let version = if field == 0 { value.clone() } else { "1".to_string() };
+                let key = ["", "distribution", "tools", "extensions", "components", "targets"][field];
+                if field == 1 {
+                    options[key] = serde_json::json!(value);
+                } else if field > 1 {
+                    options[key] = serde_json::json!([value]);
+                }
+                let config = extras_config(dev_config(), serde_json::json!({
+                    "languages": [{ "name": language, "version": version, "options": options }]
+                }));
+                prop_assert!(generate_dockerfile(&config).is_err());
+            }
+
+            #[test]
+            fn hostile_os_versions_are_rejected(
+                version in hostile(),
+                os_type in prop::sample::select(vec![
+                    "ubuntu", "debian", "alpine", "fedora", "rocky", "arch", "opensuse",
+                ]),
+            ) {
+                let config = extras_config(dev_config(), serde_json::json!({
+                    "os": { "os_type": os_type, "version": version }
+                }));
+                prop_assert!(generate_dockerfile(&config).is_err());
+            }
+
+            #[test]
+            fn hostile_names_are_rejected(value in hostile(), field in 0..7usize) {
+                let extras = match field {
+                    0 => serde_json::json!({ "packages": [value] }),
+                    1 => serde_json::json!({ "packages": [{ "apt": value }] }),
+                    2 => serde_json::json!({ "env": { value: "x" } }),
+                    3 => serde_json::json!({ "args": { value: null } }),
+                    4 => serde_json::json!({ "labels": { value: "x" } }),
+                    5 => serde_json::json!({ "user": { "name": value } }),
+                    _ => serde_json::json!({ "user": { "name": "dev", "shell": value } }),
+                };
+                prop_assert!(generate_dockerfile(&extras_config(dev_config(), extras)).is_err());
+            }
+
+            #[test]
+            fn hostile_ssh_keys_are_rejected(comment in hostile()) {
+                prop_assume!(comment.contains(['\'', '<']) || comment.contains(char::is_control));
+                let config = extras_config(dev_config(), serde_json::json!({
+                    "ssh": {
+                        "enabled": true,
+                        "port": 22,
+                        "password": "",
+                        "authorized_keys": [format!("ssh-ed25519 AAAAC3Nza {}", comment)]
+                    }
+                }));
+                prop_assert!(generate_dockerfile(&config).is_err());
+            }
+
+            /// Quoted values can hold anything but control characters without
+            /// changing the structure of the Dockerfile
+            #[test]
+            fn quoted_values_round_trip(value in "[^\\p{Cc}]*") {
+                let baseline = generate_dockerfile(&extras_config(dev_config(), serde_json::json!({
+                    "env": { "VALUE": "x" },
+                    "args": { "VALUE": "x" },
+                    "labels": { "value": "x" }
+                })))
+                .unwrap();
+                let config = extras_config(dev_config(), serde_json::json!({
+                    "env": { "VALUE": value },
+                    "args": { "VALUE": value },
+                    "labels": { "value": value }
+                }));
+                let dockerfile = generate_dockerfile(&config).unwrap();
+                prop_assert_eq!(keywords(&dockerfile), keywords(&baseline));
+
+                let imported = import_dockerfile(&dockerfile).unwrap().config;
+                prop_assert_eq!(&imported.env, &config.env);
+                prop_assert_eq!(&imported.args, &config.args);
+                prop_assert_eq!(&imported.labels, &config.labels);
+            }
+        }
+    }
 }
@@ -0,0 +1,421 @@
+//! hadolint-style checks for generated and hand-written Dockerfiles.
+//!
+//! Rule codes follow hadolint where a rule has an equivalent there, so the
+//! usual `# hadolint ignore=DL3007` comment above an instruction silences
+//! it here as well.
+
+use regex::Regex;
+use serde::Serialize;
+use std::sync::OnceLock;
+
+use crate::services::dockerfile_parser::{from_stage, parse, ParseError, ParsedInstruction};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LintFinding {
+    pub line: usize,
+    pub code: &'static str,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Package installs that leave caches behind unless cleaned up in the same layer:
+/// install pattern, cleanup commands that remove the cache, rule code
+const CACHE_RULES: &[(&str, &[&str], &str)] = &[
+    (
+        r"\bapt-get\s+(?:-\S+\s+)*install\b",
+        &["rm -rf /var/lib/apt/lists"],
+        "DL3009",
+    ),
+    (
+        r"\bapk\s+(?:-\S+\s+)*add\b",
+        &["--no-cache", "rm -rf /var/cache/apk"],
+        "DL3019",
+    ),
+    (
+        r"\bdnf\s+(?:-\S+\s+)*install\b",
+        &["dnf clean all", "rm -rf /var/cache/dnf"],
+        "DL3040",
+    ),
+    (
+        r"\bzypper\s+(?:-\S+\s+)*(?:install|in)\b",
+        &["zypper clean", "zypper cc"],
+        "DL3036",
+    ),
+    (
+        r"\bpacman\s+(?:-\S+\s+)*-S\w*\b",
+        &["rm -rf /var/cache/pacman/pkg", "pacman -Scc"],
+        "CH002",
+    ),
+];
+
+/// Lint a Dockerfile; findings are ordered by line
+pub fn lint(source: &str) -> Result<Vec<LintFinding>, ParseError> {
+    let instructions = parse(source)?;
+    let mut findings = Vec::new();
+    let mut stages: Vec<String> = Vec::new();
+    let mut last_user: Option<&ParsedInstruction> = None;
+
+    for instruction in &instructions {
+        let mut finding = |code, severity, message: String| {
+            findings.push(LintFinding {
+                line: instruction.line,
+                code,
+                severity,
+                message,
+            });
+        };
+        let arguments = instruction.arguments.as_str();
+
+        match instruction.keyword.as_str() {
+            "FROM" => {
+                last_user = None;
+                let (image, name) = from_stage(arguments);
+                if let Some(name) = name {
+                    stages.push(name.to_ascii_lowercase());
+                }
+                check_base_image(image, &stages, &mut finding);
+            }
+            "RUN" => check_run(arguments, &mut finding),
+            "USER" => last_user = Some(instruction),
+            "ADD" => {
+                let sources = arguments
+                    .split_whitespace()
+                    .filter(|w| !w.starts_with("--"));
+                let sources: Vec<&str> = sources.collect();
+                let is_remote_or_archive = |source: &&str| {
+                    source.contains("://")
+                        || [".tar", ".tar.gz", ".tgz", ".tar.xz", ".tar.bz2"]
+                            .iter()
+                            .any(|ext| source.ends_with(ext))
+                };
+                if sources.len() > 1
+                    && !sources[..sources.len() - 1]
+                        .iter()
+                        .any(is_remote_or_archive)
+                {
+                    finding(
+                        "DL3020",
+                        Severity::Error,
+                        "Use COPY instead of ADD for files and folders".to_string(),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match last_user {
+        Some(user) if is_root(&user.arguments) => findings.push(LintFinding {
+            line: user.line,
+            code: "DL3002",
+            severity: Severity::Warning,
+            message: "Last USER should not be root".to_string(),
+        }),
+        None => {
+            if let Some(from) = instructions.iter().rev().find(|i| i.keyword == "FROM") {
+                findings.push(LintFinding {
+                    line: from.line,
+                    code: "CH003",
+                    severity: Severity::Info,
+                    message: "Image runs as root; add a USER instruction for a non-root user"
+                        .to_string(),
+                });
+            }
+        }
+        _ => {}
+    }
+
+    let ignored = ignore_pragmas(source, &instructions);
+    findings.retain(|f| {
+        !ignored
+            .iter()
+            .any(|(line, codes)| *line == f.line && codes.iter().any(|c| c == f.code))
+    });
+    findings.sort_by_key(|f| f.line);
+    Ok(findings)
+}
+
+fn check_base_image(
+    image: &str,
+    stages: &[String],
+    finding: &mut impl FnMut(&'static str, Severity, String),
+) {
+    // Earlier stages, scratch and build arguments have no tag to pin
+    if image == "scratch" || image.contains('$') || stages.contains(&image.to_ascii_lowercase()) {
+        return;
+    }
+    if image.contains('@') {
+        return;
+    }
+
+    // A registry port looks like a tag, so only look after the last slash
+    let name = image.rsplit('/').next().unwrap_or(image);
+    match name.split_once(':') {
+        None => finding(
+            "DL3006",
+            Severity::Warning,
+            format!("Always tag the version of an image explicitly: {}", image),
+        ),
+        Some((_, "latest")) => finding(
+            "DL3007",
+            Severity::Warning,
+            format!(
+                "Using latest is prone to errors if the image will ever update: {}",
+                image
+            ),
+        ),
+        _ => {}
+    }
+}
+
+fn check_run(arguments: &str, finding: &mut impl FnMut(&'static str, Severity, String)) {
+    static APT_INSTALL: OnceLock<Regex> = OnceLock::new();
+    static PIPE_TO_SHELL: OnceLock<Regex> = OnceLock::new();
+    static CACHE_PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+
+    let apt_install =
+        APT_INSTALL.get_or_init(|| Regex::new(r"\bapt-get\s+(?:-\S+\s+)*install\b").unwrap());
+    let no_recommends = arguments
+        .split("&&")
+        .filter(|command| apt_install.is_match(command))
+        .any(|command| {
+            !command.contains("--no-install-recommends")
+                && !command.contains("APT::Install-Recommends=false")
+        });
+    if no_recommends {
+        finding(
+            "DL3015",
+            Severity::Info,
+            "Avoid additional packages by specifying `--no-install-recommends`".to_string(),
+        );
+    }
+
+    // Cache mounts keep the package caches out of the layer
+    if !arguments.contains("--mount=type=cache") {
+        let patterns = CACHE_PATTERNS.get_or_init(|| {
+            CACHE_RULES
+                .iter()
+                .map(|(pattern, ..)| Regex::new(pattern).unwrap())
+                .collect()
+        });
+        for ((_, cleanups, code), pattern) in CACHE_RULES.iter().zip(patterns) {
+            if pattern.is_match(arguments) && !cleanups.iter().any(|c| arguments.contains(c)) {
+                finding(
+                    code,
+                    Severity::Info,
+                    format!(
+                        "Clean up the package cache in the same RUN, e.g. `{}`",
+                        cleanups[0]
+                    ),
+                );
+            }
+        }
+    }
+
+    let pipe_to_shell = PIPE_TO_SHELL.get_or_init(|| {
+        Regex::new(r"\b(?:curl|wget)\b[^|;&]*\|\s*(?:sudo\s+)?(?:\S*/)?(?:ba|z|da)?sh\b").unwrap()
+    });
+    if pipe_to_shell.is_match(arguments) {
+        finding(
+            "CH001",
+            Severity::Warning,
+            "Do not pipe downloads into a shell; download, verify, then run the script".to_string(),
+        );
+    }
+}
+
+fn is_root(user: &str) -> bool {
+    let name = user.split(':').next().unwrap_or(user).trim();
+    name == "root" || name == "0"
+}
+
+/// `# hadolint ignore=DL3007,DL3008` comments directly above an instruction
+fn ignore_pragmas(source: &str, instructions: &[ParsedInstruction]) -> Vec<(usize, Vec<String>)> {
+    let lines: Vec<&str> = source.lines().collect();
+    instructions
+        .iter()
+        .filter_map(|instruction| {
+            let codes: Vec<String> = lines[..instruction.line - 1]
+                .iter()
+                .rev()
+                .map(|line| line.trim())
+                .take_while(|line| line.starts_with('#'))
+                .filter_map(|line| {
+                    line.trim_start_matches('#')
+                        .trim()
+                        .strip_prefix("hadolint ignore=")
+                })
+                .flat_map(|codes| codes.split(',').map(|c| c.trim().to_string()))
+                .collect();
+            (!codes.is_empty()).then_some((instruction.line, codes))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::config::EnvironmentConfig;
+    use crate::services::dockerfile_generator::generate_dockerfile;
+
+    fn codes(source: &str) -> Vec<&'static str> {
+        lint(source).unwrap().iter().map(|f| f.code).collect()
+    }
+
+    #[test]
+    fn test_base_image_tags() {
+        assert_eq!(codes("FROM ubuntu\nUSER app"), ["DL3006"]);
+        assert_eq!(codes("FROM ubuntu:latest\nUSER app"), ["DL3007"]);
+        assert!(codes("FROM localhost:5000/ubuntu:24.04\nUSER app").is_empty());
+        assert!(codes("FROM debian@sha256:abc\nUSER app").is_empty());
+        assert!(codes(
+            "FROM rust:1.83 AS build\nFROM build\nFROM scratch\nCOPY --from=build /a /a\nUSER 1000"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_apt_rules_report_line_numbers() {
+        let findings = lint(
+            "FROM debian:bookworm\n\nRUN apt-get update && \\\n    apt-get install -y curl\nUSER app",
+        )
+        .unwrap();
+        assert_eq!(findings.len(), 2);
+        assert!(findings.iter().all(|f| f.line == 3));
+        assert_eq!(findings[0].code, "DL3015");
+        assert_eq!(findings[1].code, "DL3009");
+        assert_eq!(findings[1].severity, Severity::Info);
+    }
+
+    #[test]
+    fn test_cache_cleanup_per_package_manager() {
+        assert_eq!(
+            codes("FROM alpine:3.20\nRUN apk add curl\nUSER app"),
+            ["DL3019"]
+        );
+        assert!(codes("FROM alpine:3.20\nRUN apk add --no-cache curl\nUSER app").is_empty());
+        assert_eq!(
+            codes("FROM fedora:40\nRUN dnf install -y git\nUSER app"),
+            ["DL3040"]
+        );
+        assert!(codes(
+            "FROM debian:bookworm\nRUN --mount=type=cache,target=/var/cache/apt \\\n    apt-get install -y --no-install-recommends git\nUSER app"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_pipe_to_shell() {
+        let findings = lint(
+            "FROM debian:bookworm\nRUN curl -fsSL https://example.com/install.sh | bash\nUSER app",
+        )
+        .unwrap();
+        assert_eq!(findings[0].code, "CH001");
+        assert_eq!(findings[0].severity, Severity::Warning);
+
+        assert!(codes(
+            "FROM debian:bookworm\nRUN curl -fsSL https://example.com/x.sha256 | sha256sum -c -\nUSER app"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_root_user() {
+        assert_eq!(codes("FROM debian:bookworm\nUSER root"), ["DL3002"]);
+        assert_eq!(codes("FROM debian:bookworm\nUSER 0:0"), ["DL3002"]);
+        assert_eq!(codes("FROM debian:bookworm"), ["CH003"]);
+        assert!(codes("FROM debian:bookworm\nUSER root\nRUN true\nUSER dev").is_empty());
+    }
+
+    #[test]
+    fn test_add_instead_of_copy() {
+        assert_eq!(
+            codes("FROM debian:bookworm\nADD . /app\nUSER app"),
+            ["DL3020"]
+        );
+        assert!(codes("FROM debian:bookworm\nADD rootfs.tar.gz /\nUSER app").is_empty());
+    }
+
+    #[test]
+    fn test_ignore_pragma() {
+        assert!(codes("# hadolint ignore=DL3007\nFROM ubuntu:latest\nUSER app").is_empty());
+        assert_eq!(
+            codes("# hadolint ignore=DL3006\nFROM ubuntu:latest\nUSER app"),
+            ["DL3007"]
+        );
+    }
+
+    /// Generated Dockerfiles must not trigger warnings or errors
+    #[test]
+    fn test_generated_dockerfiles_lint_clean() {
+        let os_versions = [
+            ("ubuntu", "24.04"),
+            ("debian", "bookworm"),
+            ("alpine", "latest"),
+            ("fedora", "40"),
+            ("rocky", "9"),
+            ("arch", "latest"),
+            ("opensuse", "15.6"),
+            ("opensuse", "tumbleweed"),
+        ];
+        for ((os_type, version), build_cache) in os_versions
+            .into_iter()
+            .flat_map(|os| [(os, false), (os, true)])
+        {
+            let config: EnvironmentConfig = serde_json::from_value(serde_json::json!({
+                "os": { "os_type": os_type, "version": version },
+                "languages": [
+                    { "name": "rust", "version": "stable" },
+                    { "name": "go", "version": "1.22.5" },
+                    { "name": "java", "version": "21", "options": { "tools": ["maven"] } }
+                ],
+                "ssh": { "enabled": true, "port": 2222, "password": "" },
+                "user": { "name": "dev", "sudo": true },
+                "build_cache": build_cache
+            }))
+            .unwrap();
+
+            let dockerfile = generate_dockerfile(&config).unwrap();
+            let findings = lint(&dockerfile).unwrap();
+            // SSH images keep root for sshd
+            assert!(
+                findings.iter().all(|f| f.severity == Severity::Info),
+                "{} {}: {:?}\n{}",
+                os_type,
+                version,
+                findings,
+                dockerfile
+            );
+            assert!(
+                findings.iter().all(|f| f.code == "CH003"),
+                "{} {}: {:?}",
+                os_type,
+                version,
+                findings
+            );
+        }
+
+        let config: EnvironmentConfig = serde_json::from_value(serde_json::json!({
+            "os": { "os_type": "ubuntu", "version": "22.04" },
+            "languages": [
+                { "name": "python", "version": "3.12" },
+                { "name": "nodejs", "version": "20" },
+                { "name": "ruby", "version": "3.3" },
+                { "name": "php", "version": "8.3" },
+                { "name": "perl", "version": "5.40" }
+            ],
+            "user": { "name": "dev" }
+        }))
+        .unwrap();
+        let dockerfile = generate_dockerfile(&config).unwrap();
+        assert_eq!(lint(&dockerfile).unwrap(), [], "{}", dockerfile);
+    }
+}
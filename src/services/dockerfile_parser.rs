@@ -0,0 +1,1072 @@
+//! Parse existing Dockerfiles and map what they install back onto an
+//! `EnvironmentConfig`, so hand-written images can be adopted gradually.
+
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use crate::models::config::{
+    EnvironmentConfig, Language, LanguageOptions, OsConfig, SshConfig, SystemPackage,
+};
+use crate::models::dockerfile::Instruction;
+use crate::services::distro::{Runtime, TargetOs};
+use crate::services::dockerfile_generator::{build_dockerfile, SSH_PORT_LABEL};
+use crate::services::package_manager::PackageManager;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ParseError {
+    #[error("Unknown instruction {keyword} on line {line}")]
+    UnknownInstruction { line: usize, keyword: String },
+    #[error("Dockerfile has no FROM instruction")]
+    MissingFrom,
+}
+
+const KEYWORDS: &[&str] = &[
+    "ADD",
+    "ARG",
+    "CMD",
+    "COPY",
+    "ENTRYPOINT",
+    "ENV",
+    "EXPOSE",
+    "FROM",
+    "HEALTHCHECK",
+    "LABEL",
+    "MAINTAINER",
+    "ONBUILD",
+    "RUN",
+    "SHELL",
+    "STOPSIGNAL",
+    "USER",
+    "VOLUME",
+    "WORKDIR",
+];
+
+/// One instruction with its continuation lines joined
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParsedInstruction {
+    /// 1-based line the instruction starts on
+    pub line: usize,
+    /// Upper-cased instruction keyword, e.g. `RUN`
+    pub keyword: String,
+    pub arguments: String,
+}
+
+/// Split a Dockerfile into instructions. Comments and blank lines are
+/// dropped, line continuations joined and heredoc bodies kept with the
+/// instruction that opens them.
+pub fn parse(source: &str) -> Result<Vec<ParsedInstruction>, ParseError> {
+    let mut instructions: Vec<ParsedInstruction> = Vec::new();
+    let mut escape = '\\';
+    let mut directives = true;
+    let mut current: Option<(usize, String)> = None;
+    let mut heredocs: Vec<(String, bool)> = Vec::new();
+
+    for (index, raw) in source.lines().enumerate() {
+        if let Some((terminator, strip_tabs)) = heredocs.first() {
+            let last = instructions
+                .last_mut()
+                .expect("heredoc follows an instruction");
+            last.arguments.push('\n');
+            last.arguments.push_str(raw);
+            let line = if *strip_tabs {
+                raw.trim_start_matches('\t')
+            } else {
+                raw
+            };
+            if line == terminator {
+                heredocs.remove(0);
+            }
+            continue;
+        }
+
+        let trimmed = raw.trim();
+        if let Some(comment) = trimmed.strip_prefix('#') {
+            // Parser directives are only honoured before anything else
+            if directives && current.is_none() {
+                match comment.split_once('=') {
+                    Some((key, value)) if key.trim().eq_ignore_ascii_case("escape") => {
+                        escape = value.trim().chars().next().unwrap_or(escape);
+                    }
+                    Some(_) => {}
+                    None => directives = false,
+                }
+            }
+            continue;
+        }
+        directives = false;
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        let (line, mut text) = current.take().unwrap_or((index + 1, String::new()));
+        let content = raw.trim_end();
+        match content.strip_suffix(escape) {
+            Some(continued) => {
+                text.push_str(continued);
+                current = Some((line, text));
+            }
+            None => {
+                text.push_str(content);
+                let instruction = split_instruction(line, &text)?;
+                // Only these instructions take heredocs; elsewhere `<<` is plain text
+                if matches!(instruction.keyword.as_str(), "RUN" | "COPY" | "ADD") {
+                    heredocs.extend(heredoc_terminators(&instruction.arguments));
+                }
+                instructions.push(instruction);
+            }
+        }
+    }
+
+    // A trailing continuation still ends the instruction
+    if let Some((line, text)) = current {
+        instructions.push(split_instruction(line, &text)?);
+    }
+
+    Ok(instructions)
+}
+
+fn split_instruction(line: usize, text: &str) -> Result<ParsedInstruction, ParseError> {
+    let text = text.trim();
+    let (keyword, arguments) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let keyword = keyword.to_ascii_uppercase();
+    if !KEYWORDS.contains(&keyword.as_str()) {
+        return Err(ParseError::UnknownInstruction { line, keyword });
+    }
+
+    Ok(ParsedInstruction {
+        line,
+        keyword,
+        arguments: arguments.trim().to_string(),
+    })
+}
+
+/// Frontend image named by a `# syntax=` parser directive, which BuildKit
+/// only reads from the comments at the very top of the file
+pub fn syntax_directive(source: &str) -> Option<&str> {
+    for line in source.lines() {
+        let (key, value) = line.trim().strip_prefix('#')?.split_once('=')?;
+        if key.trim().eq_ignore_ascii_case("syntax") {
+            return Some(value.trim());
+        }
+    }
+    None
+}
+
+/// Terminators of the heredocs opened by an instruction, e.g. `<<-EOF`
+fn heredoc_terminators(arguments: &str) -> Vec<(String, bool)> {
+    static HEREDOC: OnceLock<Regex> = OnceLock::new();
+    HEREDOC
+        .get_or_init(|| Regex::new(r#"<<(-?)["']?([A-Za-z_][A-Za-z0-9_]*)["']?"#).unwrap())
+        .captures_iter(arguments)
+        .map(|c| (c[2].to_string(), &c[1] == "-"))
+        .collect()
+}
+
+/// Result of importing a Dockerfile
+#[derive(Debug, Serialize)]
+pub struct ImportedDockerfile {
+    pub config: EnvironmentConfig,
+    /// Instructions with no equivalent in the configuration
+    pub unmapped: Vec<ParsedInstruction>,
+}
+
+/// Environment variables set by the language installers
+const INSTALLER_ENV: &[&str] = &[
+    "PATH",
+    "GOROOT",
+    "GOPATH",
+    "JAVA_HOME",
+    "MAVEN_HOME",
+    "GRADLE_HOME",
+    "RUSTUP_HOME",
+    "CARGO_HOME",
+    "UV_PYTHON_INSTALL_DIR",
+];
+
+/// Map a Dockerfile onto a configuration: the base OS from the first FROM,
+/// languages from official language images and recognisable install
+/// commands, SSH from an sshd setup, extra packages from install commands,
+/// and ENV, ARG and LABEL entries.
+pub fn import_dockerfile(source: &str) -> Result<ImportedDockerfile, ParseError> {
+    let instructions = parse(source)?;
+    let from = instructions
+        .iter()
+        .position(|i| i.keyword == "FROM")
+        .ok_or(ParseError::MissingFrom)?;
+
+    let mut config = EnvironmentConfig::default();
+    let mut mapped = vec![false; instructions.len()];
+
+    let image = base_image(&instructions[from].arguments);
+    if let Some((os, language)) = map_base_image(image) {
+        config.os = os;
+        config.languages.extend(language);
+        mapped[from] = true;
+    }
+    let target = TargetOs::from_config(&config.os).ok();
+
+    // Only the first stage is imported; later stages stay unmapped
+    let stage_end = instructions[from + 1..]
+        .iter()
+        .position(|i| i.keyword == "FROM")
+        .map_or(instructions.len(), |end| from + 1 + end);
+
+    for instruction in &instructions[from + 1..stage_end] {
+        if instruction.keyword != "RUN" {
+            continue;
+        }
+        for language in detect_languages(&instruction.arguments, target.as_ref()) {
+            if !config.languages.iter().any(|l| l.name == language.name) {
+                config.languages.push(language);
+            }
+        }
+        if let Some(ssh) = detect_ssh(&instruction.arguments) {
+            // The server package and its configuration may be set up in separate layers
+            config.ssh = Some(match config.ssh.take() {
+                Some(previous) => SshConfig {
+                    port: if ssh.port == 0 {
+                        previous.port
+                    } else {
+                        ssh.port
+                    },
+                    authorized_keys: [previous.authorized_keys, ssh.authorized_keys].concat(),
+                    disable_password_auth: previous.disable_password_auth
+                        || ssh.disable_password_auth,
+                    ..ssh
+                },
+                None => ssh,
+            });
+        }
+        if instruction.arguments.contains("--mount=type=cache") {
+            config.build_cache = true;
+        }
+    }
+
+    for (index, instruction) in instructions
+        .iter()
+        .enumerate()
+        .take(stage_end)
+        .skip(from + 1)
+    {
+        let arguments = instruction.arguments.as_str();
+        let ssh = config.ssh.as_mut();
+        mapped[index] |= match instruction.keyword.as_str() {
+            "WORKDIR" => arguments == "/app",
+            "ENV" => {
+                let pairs = env_pairs(arguments);
+                for (key, value) in &pairs {
+                    // Installer paths come back with the languages
+                    if config.languages.is_empty() || !INSTALLER_ENV.contains(&key.as_str()) {
+                        config.env.insert(key.clone(), value.clone());
+                    }
+                }
+                !pairs.is_empty()
+            }
+            "ARG" => match arguments.split_once('=') {
+                Some((name, value)) => {
+                    let value: String = shell_words(value).into_iter().map(|(w, _)| w).collect();
+                    config.args.insert(name.to_string(), Some(value));
+                    true
+                }
+                None if !arguments.contains(char::is_whitespace) => {
+                    config.args.insert(arguments.to_string(), None);
+                    true
+                }
+                None => false,
+            },
+            "EXPOSE" => match (ssh, arguments.split('/').next().unwrap_or("").parse()) {
+                // sshd_config wins over EXPOSE when both name a port
+                (Some(ssh), Ok(port)) => {
+                    if ssh.port == 0 {
+                        ssh.port = port;
+                    }
+                    ssh.port == port
+                }
+                _ => false,
+            },
+            "LABEL" => {
+                let pairs = env_pairs(arguments);
+                for (key, value) in &pairs {
+                    if key != SSH_PORT_LABEL {
+                        config.labels.insert(key.clone(), value.clone());
+                    }
+                }
+                !pairs.is_empty()
+            }
+            "ENTRYPOINT" => ssh.is_some() && arguments.contains("/entrypoint.sh"),
+            "CMD" => {
+                arguments.contains("/bin/bash") || (ssh.is_some() && arguments.contains("sshd"))
+            }
+            _ => false,
+        };
+    }
+    if let Some(ssh) = config.ssh.as_mut().filter(|s| s.port == 0) {
+        ssh.port = 22;
+    }
+
+    // A RUN is mapped when every command in it is one the generator emits
+    // for what was detected, or installs packages that become extras
+    if let Some(target) = &target {
+        let package_manager = target.package_manager();
+        let known = KnownCommands::new(&config, package_manager);
+        for (index, instruction) in instructions
+            .iter()
+            .enumerate()
+            .take(stage_end)
+            .skip(from + 1)
+        {
+            if instruction.keyword != "RUN" {
+                continue;
+            }
+            let mut recognized = true;
+            for command in shell_commands(run_command(&instruction.arguments)) {
+                if known.commands.contains(&command) {
+                    continue;
+                }
+                let Some(packages) = install_arguments(&command, package_manager) else {
+                    recognized = false;
+                    continue;
+                };
+                for package in packages
+                    .into_iter()
+                    .filter(|p| !known.packages.contains(*p))
+                {
+                    let package = SystemPackage::Name(package.to_string());
+                    if !config.packages.contains(&package) {
+                        config.packages.push(package);
+                    }
+                }
+            }
+            mapped[index] = recognized;
+        }
+    }
+
+    let unmapped = instructions
+        .into_iter()
+        .zip(mapped)
+        .filter(|(_, mapped)| !mapped)
+        .map(|(instruction, _)| instruction)
+        .collect();
+
+    Ok(ImportedDockerfile { config, unmapped })
+}
+
+/// Commands and installed packages of the Dockerfiles the generator emits
+/// for the OS, languages and SSH server found in an imported Dockerfile
+#[derive(Default)]
+struct KnownCommands {
+    commands: HashSet<String>,
+    packages: HashSet<String>,
+}
+
+impl KnownCommands {
+    fn new(config: &EnvironmentConfig, package_manager: PackageManager) -> Self {
+        let base = || EnvironmentConfig {
+            os: config.os.clone(),
+            build_cache: config.build_cache,
+            ..Default::default()
+        };
+        // One Dockerfile per part, so a version the generator rejects only
+        // leaves that part's commands unknown
+        let mut configs = vec![base()];
+        configs.extend(config.languages.iter().map(|language| EnvironmentConfig {
+            languages: vec![language.clone()],
+            ..base()
+        }));
+        configs.extend(config.ssh.iter().map(|ssh| EnvironmentConfig {
+            ssh: Some(ssh.clone()),
+            ..base()
+        }));
+
+        let mut known = Self::default();
+        let housekeeping = [package_manager.update(), package_manager.cleanup()];
+        for command in housekeeping.iter().chain(package_manager.keep_cache()) {
+            known.commands.extend(shell_commands(command));
+        }
+        for dockerfile in configs.iter().filter_map(|c| build_dockerfile(c).ok()) {
+            for instruction in dockerfile.sections.iter().flat_map(|s| &s.instructions) {
+                let Instruction::Run { commands, .. } = instruction else {
+                    continue;
+                };
+                for command in commands.iter().flat_map(|c| shell_commands(c)) {
+                    if let Some(packages) = install_arguments(&command, package_manager) {
+                        known
+                            .packages
+                            .extend(packages.into_iter().map(str::to_string));
+                    }
+                    known.commands.insert(command);
+                }
+            }
+        }
+        known
+    }
+}
+
+/// Shell command of a RUN instruction without its `--mount` and other flags
+fn run_command(arguments: &str) -> &str {
+    let mut command = arguments.trim_start();
+    while command.starts_with("--") {
+        command = command
+            .split_once(char::is_whitespace)
+            .map_or("", |(_, rest)| rest.trim_start());
+    }
+    command
+}
+
+/// Split a shell line on unquoted `&&`, `;` and newlines, collapsing the
+/// whitespace of each command so they compare equal however they are wrapped
+fn shell_commands(line: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    let mut start = 0;
+    let mut quote = None;
+    let mut chars = line.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None | Some('"'), '\\') => {
+                chars.next();
+            }
+            (None, ';' | '\n') => {
+                commands.push(&line[start..index]);
+                start = index + 1;
+            }
+            (None, '&') if chars.next_if(|&(_, c)| c == '&').is_some() => {
+                commands.push(&line[start..index]);
+                start = index + 2;
+            }
+            _ => {}
+        }
+    }
+    commands.push(&line[start..]);
+    commands
+        .into_iter()
+        .map(|command| command.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|command| !command.is_empty())
+        .collect()
+}
+
+/// Packages of an install command for `package_manager`, e.g. `git` and
+/// `jq` for `apt-get install -y git jq`
+fn install_arguments(command: &str, package_manager: PackageManager) -> Option<Vec<&str>> {
+    let (binaries, subcommands): (&[&str], &[&str]) = match package_manager {
+        PackageManager::Apt => (&["apt-get", "apt"], &["install"]),
+        PackageManager::Apk => (&["apk"], &["add"]),
+        PackageManager::Dnf => (&["dnf", "yum", "microdnf"], &["install"]),
+        PackageManager::Zypper => (&["zypper"], &["install", "in"]),
+        PackageManager::Pacman => (&["pacman"], &[]),
+    };
+    // Variables such as DEBIAN_FRONTEND may be set for the command
+    let mut words = command
+        .split_whitespace()
+        .skip_while(|word| !word.starts_with('-') && word.contains('='));
+    if !binaries.contains(&words.next()?) {
+        return None;
+    }
+    let (flags, operands): (Vec<&str>, Vec<&str>) = words.partition(|word| word.starts_with('-'));
+    match package_manager {
+        PackageManager::Pacman => flags
+            .iter()
+            .any(|flag| flag.starts_with("-S"))
+            .then_some(operands),
+        _ => match operands.split_first() {
+            Some((subcommand, packages)) if subcommands.contains(subcommand) => {
+                Some(packages.to_vec())
+            }
+            _ => None,
+        },
+    }
+}
+
+/// Image reference and stage name of a FROM instruction, e.g.
+/// `--platform=$BUILDPLATFORM rust:1 AS build` gives `rust:1` and `build`
+pub fn from_stage(arguments: &str) -> (&str, Option<&str>) {
+    let mut words = arguments
+        .split_whitespace()
+        .filter(|word| !word.starts_with("--"));
+    let image = words.next().unwrap_or("");
+    let name = match (words.next(), words.next()) {
+        (Some(as_keyword), Some(name)) if as_keyword.eq_ignore_ascii_case("as") => Some(name),
+        _ => None,
+    };
+    (image, name)
+}
+
+/// Image reference of a FROM instruction without flags, digest or stage name
+fn base_image(arguments: &str) -> &str {
+    let (image, _) = from_stage(arguments);
+    let image = image.split('@').next().unwrap_or(image);
+    let image = image.strip_prefix("docker.io/").unwrap_or(image);
+    image.strip_prefix("library/").unwrap_or(image)
+}
+
+/// Official language images, the language they provide and their default base
+const LANGUAGE_IMAGES: &[(&str, &str, &str, &str)] = &[
+    ("python", "python", "debian", "bookworm"),
+    ("node", "nodejs", "debian", "bookworm"),
+    ("golang", "go", "debian", "bookworm"),
+    ("rust", "rust", "debian", "bookworm"),
+    ("ruby", "ruby", "debian", "bookworm"),
+    ("php", "php", "debian", "bookworm"),
+    ("perl", "perl", "debian", "bookworm"),
+    ("eclipse-temurin", "java", "ubuntu", "24.04"),
+];
+
+fn map_base_image(image: &str) -> Option<(OsConfig, Option<Language>)> {
+    let (name, tag) = image.split_once(':').unwrap_or((image, "latest"));
+    let os = |os_type: &str, version: &str| OsConfig {
+        os_type: os_type.to_string(),
+        version: version.to_string(),
+    };
+
+    let os_type = match name {
+        "ubuntu" | "debian" | "alpine" | "fedora" => name,
+        "rockylinux" | "rockylinux/rockylinux" => "rocky",
+        "archlinux" => "arch",
+        "opensuse/leap" => "opensuse",
+        "opensuse/tumbleweed" => return Some((os("opensuse", "tumbleweed"), None)),
+        _ => {
+            let &(_, language, default_os, default_version) =
+                LANGUAGE_IMAGES.iter().find(|(image, ..)| *image == name)?;
+            return Some(map_language_image(
+                language,
+                tag,
+                os(default_os, default_version),
+            ));
+        }
+    };
+
+    Some((os(os_type, tag), None))
+}
+
+/// Language images are tagged `<version>[-variant][-<base>]`, e.g.
+/// `3.12-slim-bookworm`, `20-alpine3.20` or `21-jdk-jammy`
+fn map_language_image(
+    language: &str,
+    tag: &str,
+    default_os: OsConfig,
+) -> (OsConfig, Option<Language>) {
+    let mut parts = tag.split('-');
+    let version = parts.next().unwrap_or(tag);
+    let mut os = default_os;
+
+    for part in parts {
+        if let Some(release) = part.strip_prefix("alpine") {
+            os.os_type = "alpine".to_string();
+            os.version = if release.is_empty() {
+                "latest"
+            } else {
+                release
+            }
+            .to_string();
+        } else if let Some(release) = crate::services::distro::debian::release(part) {
+            os.os_type = "debian".to_string();
+            os.version = release.codename.to_string();
+        } else if let Some(release) = crate::services::distro::ubuntu::release(part) {
+            os.os_type = "ubuntu".to_string();
+            os.version = release.version.to_string();
+        }
+    }
+
+    // Floating tags such as `latest` or `lts` do not pin a version
+    let language = version
+        .starts_with(|c: char| c.is_ascii_digit())
+        .then(|| Language {
+            name: language.to_string(),
+            version: version.to_string(),
+            options: LanguageOptions::default(),
+        });
+    (os, language)
+}
+
+/// Install commands recognised per language. A pattern's capture groups
+/// are joined with `.` to form the version (so `php83` becomes `8.3`); a
+/// pattern without groups means the distribution's packaged version.
+struct Detector {
+    language: &'static str,
+    patterns: Vec<Regex>,
+    packaged: Option<Runtime>,
+}
+
+fn detectors() -> &'static [Detector] {
+    static DETECTORS: OnceLock<Vec<Detector>> = OnceLock::new();
+    DETECTORS.get_or_init(|| {
+        let detector = |language, patterns: &[&str], packaged| Detector {
+            language,
+            patterns: patterns.iter().map(|p| Regex::new(p).unwrap()).collect(),
+            packaged,
+        };
+        vec![
+            detector(
+                "python",
+                &[
+                    r"uv python install (\d+\.\d+(?:\.\d+)?)",
+                    r"python3~(\d+\.\d+)",
+                    r"\bpython(3\.\d+)\b",
+                    r"\bpython3-pip\b",
+                ],
+                Some(Runtime::Python),
+            ),
+            detector(
+                "nodejs",
+                &[
+                    r"nodesource\.com/setup_(\d+)\.x",
+                    r"\bnodejs(?:-current)?~(\d+)",
+                    r"NODE_VERSION=v(\d+(?:\.\d+)*)",
+                    r#""version":"v(\d+)"#,
+                    r"\bnodejs(\d+)\b",
+                ],
+                None,
+            ),
+            detector("go", &[r"\bgo(\d+\.\d+(?:\.\d+)?)\.linux-"], None),
+            detector(
+                "java",
+                &[
+                    r"/v3/binary/latest/(\d+)/",
+                    r"\bopenjdk-(\d+)-jdk",
+                    r"\bopenjdk(\d+)-jdk",
+                    r"\bjava-(\d+)-openjdk",
+                    r"\bjdk(\d+)-openjdk",
+                ],
+                None,
+            ),
+            detector(
+                "ruby",
+                &[
+                    r"RUBY_VERSION=(\d+\.\d+\.\d+)",
+                    r"grep -E '\^(\d+)\\\.(\d+)\\\.\[0-9\]",
+                    r"\bruby~(\d+\.\d+)",
+                    r"\bruby-full\b",
+                ],
+                Some(Runtime::Ruby),
+            ),
+            detector("php", &[r"\bphp(\d+\.\d+)-cli\b", r"\bphp(\d)(\d)\b"], None),
+            detector(
+                "perl",
+                &[
+                    r"PERL_TARBALL=perl-(5\.\d+\.\d+)\.tar",
+                    r"'perl-(5)\\\.(\d+)\\\.\[0-9\]",
+                    r"\bperl~(5\.\d+)",
+                    r"\bcpanminus\b",
+                ],
+                Some(Runtime::Perl),
+            ),
+        ]
+    })
+}
+
+fn detect_languages(command: &str, target: Option<&TargetOs>) -> Vec<Language> {
+    let mut languages: Vec<Language> = detectors()
+        .iter()
+        .filter_map(|detector| {
+            let version = detector.patterns.iter().find_map(|pattern| {
+                let captures = pattern.captures(command)?;
+                if captures.len() == 1 {
+                    let packaged = target?.packaged_version(detector.packaged?)?;
+                    return Some(packaged.to_string());
+                }
+                let groups: Vec<&str> = captures
+                    .iter()
+                    .skip(1)
+                    .flatten()
+                    .map(|m| m.as_str())
+                    .collect();
+                Some(groups.join("."))
+            })?;
+            Some(Language {
+                name: detector.language.to_string(),
+                version,
+                options: LanguageOptions::default(),
+            })
+        })
+        .collect();
+
+    if command.contains("rustup") {
+        languages.push(detect_rust(command));
+    }
+    for language in &mut languages {
+        if language.name == "java" {
+            language.options = detect_java_options(command);
+        }
+    }
+    languages
+}
+
+fn detect_rust(command: &str) -> Language {
+    static FLAG: OnceLock<Regex> = OnceLock::new();
+
+    let flags =
+        FLAG.get_or_init(|| Regex::new(r"--(default-toolchain|component|target) (\S+)").unwrap());
+    let flag = |name: &str| {
+        flags
+            .captures_iter(command)
+            .find(|c| &c[1] == name)
+            .map(|c| c[2].to_string())
+    };
+    let list = |value: Option<String>| -> Vec<String> {
+        value
+            .map(|v| v.split(',').map(str::to_string).collect())
+            .unwrap_or_default()
+    };
+
+    Language {
+        name: "rust".to_string(),
+        version: flag("default-toolchain").unwrap_or_else(|| "stable".to_string()),
+        options: LanguageOptions {
+            components: list(flag("component")),
+            targets: list(flag("target")),
+            ..Default::default()
+        },
+    }
+}
+
+fn detect_java_options(command: &str) -> LanguageOptions {
+    static MAVEN: OnceLock<Regex> = OnceLock::new();
+    static GRADLE: OnceLock<Regex> = OnceLock::new();
+
+    let distribution = (!command.contains("api.adoptium.net")).then(|| "openjdk".to_string());
+    let tools = [
+        (
+            "maven",
+            MAVEN.get_or_init(|| Regex::new(r"apache-maven-([\d.]+)-bin").unwrap()),
+        ),
+        (
+            "gradle",
+            GRADLE.get_or_init(|| Regex::new(r"gradle-([\d.]+)-bin").unwrap()),
+        ),
+    ]
+    .iter()
+    .filter_map(|(tool, pattern)| {
+        let captures = pattern.captures(command)?;
+        Some(format!("{}@{}", tool, &captures[1]))
+    })
+    .collect();
+
+    LanguageOptions {
+        distribution,
+        tools,
+        ..Default::default()
+    }
+}
+
+fn detect_ssh(command: &str) -> Option<SshConfig> {
+    static OPENSSH: OnceLock<Regex> = OnceLock::new();
+    static PORT: OnceLock<Regex> = OnceLock::new();
+    static KEY: OnceLock<Regex> = OnceLock::new();
+
+    let installs_server = OPENSSH
+        .get_or_init(|| Regex::new(r"\b(?:apt-get install|apk add|dnf install|zypper (?:--non-interactive )?install|pacman -S)\b.*\bopenssh(?:-server)?\b").unwrap())
+        .is_match(command);
+    if !installs_server && !command.contains("/etc/ssh/sshd_config") {
+        return None;
+    }
+
+    Some(SshConfig {
+        enabled: true,
+        // Filled in from EXPOSE when sshd_config is left alone
+        port: PORT
+            .get_or_init(|| Regex::new(r"\bPort (\d+)\b").unwrap())
+            .captures(command)
+            .and_then(|c| c[1].parse().ok())
+            .unwrap_or(0),
+        authorized_keys: KEY
+            .get_or_init(|| {
+                Regex::new(r"(?:ssh-ed25519|ssh-rsa|ecdsa-sha2-nistp\d+|sk-[a-z0-9-]+@openssh\.com) [A-Za-z0-9+/=]+(?: [^'\s]+)?").unwrap()
+            })
+            .find_iter(command)
+            .map(|m| m.as_str().to_string())
+            .collect(),
+        disable_password_auth: command.contains("PasswordAuthentication no"),
+        ..Default::default()
+    })
+}
+
+/// Pairs of an ENV or LABEL instruction (`KEY=value ...` or `KEY value`)
+fn env_pairs(arguments: &str) -> Vec<(String, String)> {
+    let words = shell_words(arguments);
+    match words.first() {
+        // The legacy form takes the rest of the line verbatim
+        Some((key, end)) if !key.contains('=') => {
+            vec![(key.clone(), arguments[*end..].trim().to_string())]
+        }
+        _ => words
+            .iter()
+            .filter_map(|(word, _)| word.split_once('='))
+            .filter(|(key, _)| !key.is_empty())
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+    }
+}
+
+/// Split on unquoted whitespace and remove quotes and escapes. Each word
+/// comes with the byte offset in `arguments` where its raw text ends.
+fn shell_words(arguments: &str) -> Vec<(String, usize)> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut chars = arguments.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match (quote, c) {
+            (None, c) if c.is_whitespace() => words.extend(word.take().map(|w| (w, index))),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (Some(q), c) if c == q => quote = None,
+            (None | Some('"'), '\\') => {
+                let escaped = chars.next().map_or('\\', |(_, c)| c);
+                word.get_or_insert_with(String::new).push(escaped);
+            }
+            (_, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word.map(|w| (w, arguments.len())));
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::dockerfile_generator::generate_dockerfile;
+
+    #[test]
+    fn test_parse_continuations_and_comments() {
+        let instructions = parse(
+            "# syntax=docker/dockerfile:1\nFROM debian:bookworm\n\nRUN apt-get update && \\\n    # inline comment\n    apt-get install -y curl\nworkdir /app\n",
+        )
+        .unwrap();
+        assert_eq!(instructions.len(), 3);
+        assert_eq!(instructions[1].line, 4);
+        assert_eq!(instructions[1].keyword, "RUN");
+        assert_eq!(
+            instructions[1].arguments,
+            "apt-get update &&     apt-get install -y curl"
+        );
+        assert_eq!(instructions[2].keyword, "WORKDIR");
+    }
+
+    #[test]
+    fn test_syntax_directive() {
+        assert_eq!(
+            syntax_directive("# escape=`\n# Syntax = docker/dockerfile:1.7\nFROM alpine"),
+            Some("docker/dockerfile:1.7")
+        );
+        assert_eq!(
+            syntax_directive("# build image\n# syntax=a/b\nFROM alpine"),
+            None
+        );
+        assert_eq!(syntax_directive("FROM alpine\n# syntax=a/b"), None);
+    }
+
+    #[test]
+    fn test_parse_escape_directive_and_heredoc() {
+        let instructions = parse(
+            "# escape=`\nFROM alpine\nRUN echo a `\n    b\nRUN <<EOF\necho one\nFROM not-an-instruction\nEOF\nCMD [\"sh\"]",
+        )
+        .unwrap();
+        assert_eq!(instructions[1].arguments, "echo a     b");
+        assert_eq!(
+            instructions[2].arguments,
+            "<<EOF\necho one\nFROM not-an-instruction\nEOF"
+        );
+        assert_eq!(instructions[3].keyword, "CMD");
+        assert_eq!(instructions[3].line, 9);
+    }
+
+    #[test]
+    fn test_parse_unknown_instruction() {
+        let err = parse("FROM alpine\nRUNN true").unwrap_err();
+        assert_eq!(err.to_string(), "Unknown instruction RUNN on line 2");
+    }
+
+    #[test]
+    fn test_import_language_image() {
+        let imported = import_dockerfile(
+            "FROM python:3.12-slim-bookworm\nWORKDIR /app\nCOPY . .\nCMD [\"python\", \"app.py\"]",
+        )
+        .unwrap();
+        assert_eq!(imported.config.os.os_type, "debian");
+        assert_eq!(imported.config.os.version, "bookworm");
+        assert_eq!(imported.config.languages[0].name, "python");
+        assert_eq!(imported.config.languages[0].version, "3.12");
+
+        let unmapped: Vec<&str> = imported
+            .unmapped
+            .iter()
+            .map(|i| i.keyword.as_str())
+            .collect();
+        assert_eq!(unmapped, ["COPY", "CMD"]);
+    }
+
+    #[test]
+    fn test_import_alpine_language_image() {
+        let imported = import_dockerfile("FROM node:20-alpine3.20").unwrap();
+        assert_eq!(imported.config.os.os_type, "alpine");
+        assert_eq!(imported.config.os.version, "3.20");
+        assert_eq!(imported.config.languages[0].name, "nodejs");
+    }
+
+    #[test]
+    fn test_import_hand_written_dockerfile() {
+        let imported = import_dockerfile(
+            "FROM --platform=linux/amd64 docker.io/library/ubuntu:22.04\n\
+             RUN apt-get update && apt-get install -y openssh-server python3 python3-pip\n\
+             RUN pip install poetry\n\
+             EXPOSE 2222\n\
+             USER app\n\
+             CMD [\"/usr/sbin/sshd\", \"-D\"]",
+        )
+        .unwrap();
+        assert_eq!(imported.config.os.os_type, "ubuntu");
+        assert_eq!(imported.config.os.version, "22.04");
+        assert_eq!(imported.config.languages[0].version, "3.10");
+        let ssh = imported.config.ssh.unwrap();
+        assert!(ssh.enabled);
+        assert_eq!(ssh.port, 2222);
+
+        let unmapped: Vec<usize> = imported.unmapped.iter().map(|i| i.line).collect();
+        assert_eq!(unmapped, [3, 5]);
+    }
+
+    #[test]
+    fn test_import_mixed_run() {
+        let imported = import_dockerfile(
+            "FROM debian:bookworm\n\
+             RUN apt-get update && apt-get install -y python3-pip git jq && ./setup.sh\n\
+             RUN apt-get update && apt-get install -y --no-install-recommends curl; rm -rf /var/lib/apt/lists/*",
+        )
+        .unwrap();
+        assert_eq!(imported.config.languages[0].name, "python");
+        assert_eq!(
+            imported.config.packages,
+            ["git", "jq", "curl"].map(|p| SystemPackage::Name(p.to_string()))
+        );
+
+        let unmapped: Vec<usize> = imported.unmapped.iter().map(|i| i.line).collect();
+        assert_eq!(unmapped, [2]);
+    }
+
+    #[test]
+    fn test_import_unknown_base_image() {
+        let imported =
+            import_dockerfile("FROM mcr.microsoft.com/dotnet/sdk:8.0\nRUN dotnet --info").unwrap();
+        assert!(imported.config.os.os_type.is_empty());
+        assert_eq!(imported.unmapped.len(), 2);
+
+        assert!(matches!(
+            import_dockerfile("ARG VERSION=1"),
+            Err(ParseError::MissingFrom)
+        ));
+    }
+
+    #[test]
+    fn test_env_pairs() {
+        assert_eq!(
+            env_pairs(r#"A=1 B="two words" C='$HOME' D=escaped\ space"#),
+            [
+                ("A".to_string(), "1".to_string()),
+                ("B".to_string(), "two words".to_string()),
+                ("C".to_string(), "$HOME".to_string()),
+                ("D".to_string(), "escaped space".to_string()),
+            ]
+        );
+        assert_eq!(
+            env_pairs("GREETING hello world"),
+            [("GREETING".to_string(), "hello world".to_string())]
+        );
+        assert_eq!(env_pairs(r#""A" b"#), [("A".to_string(), "b".to_string())]);
+        assert_eq!(
+            env_pairs(r#""ü\ x" é"#),
+            [("ü x".to_string(), "é".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_import_quoted_non_ascii_keys() {
+        let imported = import_dockerfile("FROM alpine:3.20\nENV \"ü\" x\nLABEL \"é\" y").unwrap();
+        assert_eq!(imported.config.env["ü"], "x");
+        assert_eq!(imported.config.labels["é"], "y");
+        assert!(imported.unmapped.is_empty());
+    }
+
+    #[test]
+    fn test_import_generated_dockerfile_round_trip() {
+        let config: EnvironmentConfig = serde_json::from_value(serde_json::json!({
+            "os": { "os_type": "debian", "version": "bookworm" },
+            "languages": [
+                { "name": "python", "version": "3.12" },
+                { "name": "nodejs", "version": "20" },
+                { "name": "rust", "version": "1.83.0", "options": { "components": ["clippy"] } },
+                { "name": "go", "version": "1.22.5" },
+                { "name": "java", "version": "21", "options": { "tools": ["maven@3.9.9"] } },
+                { "name": "ruby", "version": "3.3" },
+                { "name": "php", "version": "8.3" },
+                { "name": "perl", "version": "5.40" }
+            ],
+            "ssh": {
+                "enabled": true,
+                "port": 2222,
+                "password": "",
+                "authorized_keys": ["ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIGxA3ZsPq0t7tJvHdxw0yXqUrkUl0vmQ1HhBhwGlC4B7 dev@laptop"],
+                "disable_password_auth": true
+            },
+            "build_cache": true,
+            "packages": ["git"],
+            "env": { "EDITOR": "vim", "GREETING": "say \"hi\"" },
+            "args": { "APP_VERSION": "1.0", "TOKEN": null },
+            "labels": { "org.opencontainers.image.title": "dev env" }
+        }))
+        .unwrap();
+
+        let imported = import_dockerfile(&generate_dockerfile(&config).unwrap()).unwrap();
+        assert!(imported.unmapped.is_empty(), "{:?}", imported.unmapped);
+        assert_eq!(imported.config.os.os_type, "debian");
+        assert_eq!(imported.config.os.version, "bookworm");
+        assert!(imported.config.build_cache);
+        assert_eq!(imported.config.packages, config.packages);
+        assert_eq!(imported.config.env, config.env);
+        assert_eq!(imported.config.args, config.args);
+        assert_eq!(imported.config.labels, config.labels);
+
+        let languages: Vec<(&str, &str)> = imported
+            .config
+            .languages
+            .iter()
+            .map(|l| (l.name.as_str(), l.version.as_str()))
+            .collect();
+        for expected in config
+            .languages
+            .iter()
+            .map(|l| (l.name.as_str(), l.version.as_str()))
+        {
+            assert!(
+                languages.contains(&expected),
+                "{:?} not in {:?}",
+                expected,
+                languages
+            );
+        }
+
+        let rust = imported
+            .config
+            .languages
+            .iter()
+            .find(|l| l.name == "rust")
+            .unwrap();
+        assert_eq!(rust.options.components, ["clippy"]);
+        let java = imported
+            .config
+            .languages
+            .iter()
+            .find(|l| l.name == "java")
+            .unwrap();
+        assert_eq!(java.options.tools, ["maven@3.9.9"]);
+
+        let ssh = imported.config.ssh.unwrap();
+        assert_eq!(ssh.port, 2222);
+        assert!(ssh.disable_password_auth);
+        assert_eq!(ssh.authorized_keys, config.ssh.unwrap().authorized_keys);
+    }
+}
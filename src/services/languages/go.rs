@@ -0,0 +1,111 @@
+use super::{is_numeric_version, unavailable, LanguageInstaller};
+use crate::models::config::Language;
+use crate::services::distro::TargetOs;
+use crate::services::dockerfile_generator::GeneratorError;
+use crate::services::package_manager::InstallPlan;
+
+pub struct GoInstaller;
+
+const DOWNLOAD_URL: &str = "https://dl.google.com/go";
+const GOROOT: &str = "/usr/local/go";
+const GOPATH: &str = "/go";
+
+impl LanguageInstaller for GoInstaller {
+    fn name(&self) -> &'static str {
+        "go"
+    }
+
+    fn install_plan(
+        &self,
+        language: &Language,
+        target: &TargetOs,
+    ) -> Result<InstallPlan, GeneratorError> {
+        let version = language.version.as_str();
+        if !is_release_version(version) {
+            return Err(unavailable(language, target));
+        }
+
+        // The release tarballs are statically linked, so the same download
+        // works on glibc and musl bases; only the CPU architecture differs.
+        let tarball = format!("go{}.linux-${{GO_ARCH}}.tar.gz", version);
+
+        Ok(
+            InstallPlan::packages(&["ca-certificates", "curl", "tar", "gzip"])
+                .step(
+                    "case \"$(uname -m)\" in x86_64) GO_ARCH=amd64 ;; aarch64) GO_ARCH=arm64 ;; \
+                     *) echo \"Unsupported architecture: $(uname -m)\" >&2; exit 1 ;; esac",
+                )
+                .step(format!(
+                    "curl -fsSLo go.tar.gz {}/{}",
+                    DOWNLOAD_URL, tarball
+                ))
+                .step(format!(
+                    "echo \"$(curl -fsSL {}/{}.sha256)  go.tar.gz\" | sha256sum -c -",
+                    DOWNLOAD_URL, tarball
+                ))
+                .step(format!(
+                    "rm -rf {} && tar -C /usr/local -xzf go.tar.gz",
+                    GOROOT
+                ))
+                .step("rm go.tar.gz")
+                .step(format!(
+                    "mkdir -p {0}/bin {0}/src && chmod -R 1777 {0}",
+                    GOPATH
+                ))
+                .env("GOROOT", GOROOT)
+                .env("GOPATH", GOPATH)
+                .env("PATH", format!("{}/bin:{}/bin:${{PATH}}", GOROOT, GOPATH)),
+        )
+    }
+}
+
+/// Release tarballs are named after full versions such as `1.22.5`
+/// (or `1.20` for the first release of a series before Go 1.21)
+fn is_release_version(version: &str) -> bool {
+    (2..=3).contains(&version.split('.').count()) && is_numeric_version(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::languages::testing::{language, render, target};
+
+    #[test]
+    fn test_go_downloads_verified_tarball() {
+        let dockerfile = render(
+            &GoInstaller,
+            &language("go", "1.22.5"),
+            &target("debian", "bookworm"),
+        );
+        assert!(dockerfile.contains(
+            "curl -fsSLo go.tar.gz https://dl.google.com/go/go1.22.5.linux-${GO_ARCH}.tar.gz"
+        ));
+        assert!(dockerfile
+            .contains("go1.22.5.linux-${GO_ARCH}.tar.gz.sha256)  go.tar.gz\" | sha256sum -c -"));
+        assert!(dockerfile.contains("ENV GOROOT=\"/usr/local/go\""));
+        assert!(dockerfile.contains("ENV GOPATH=\"/go\""));
+        assert!(dockerfile.contains("ENV PATH=\"/usr/local/go/bin:/go/bin:${PATH}\""));
+    }
+
+    #[test]
+    fn test_go_musl_base() {
+        let dockerfile = render(
+            &GoInstaller,
+            &language("go", "1.23.4"),
+            &target("alpine", "3.20"),
+        );
+        assert!(dockerfile.contains("apk add ca-certificates curl tar gzip"));
+        assert!(dockerfile.contains("aarch64) GO_ARCH=arm64"));
+        assert!(dockerfile.contains("go1.23.4.linux-${GO_ARCH}.tar.gz"));
+    }
+
+    #[test]
+    fn test_go_rejects_partial_versions() {
+        for version in ["1", "latest", "1.22.x", "1..2"] {
+            assert!(GoInstaller
+                .install_plan(&language("go", version), &target("ubuntu", "22.04"))
+                .is_err());
+        }
+        assert!(is_release_version("1.20"));
+    }
+}
@@ -0,0 +1,292 @@
+use super::{is_numeric_version, unavailable, LanguageInstaller};
+use crate::models::config::Language;
+use crate::services::distro::TargetOs;
+use crate::services::dockerfile_generator::GeneratorError;
+use crate::services::package_manager::{InstallPlan, PackageManager};
+
+pub struct JavaInstaller;
+
+const JAVA_HOME: &str = "/opt/java";
+const MAVEN_VERSION: &str = "3.9.9";
+const GRADLE_VERSION: &str = "8.10.2";
+
+impl LanguageInstaller for JavaInstaller {
+    fn name(&self) -> &'static str {
+        "java"
+    }
+
+    fn install_plan(
+        &self,
+        language: &Language,
+        target: &TargetOs,
+    ) -> Result<InstallPlan, GeneratorError> {
+        let version = language.version.as_str();
+        if version.is_empty() || !version.chars().all(|c| c.is_ascii_digit()) {
+            return Err(unavailable(language, target));
+        }
+
+        let mut plan = match language.options.distribution.as_deref() {
+            None | Some("temurin") => temurin_plan(version, target),
+            Some("openjdk") => {
+                // Only the releases we have metadata for are checked up front
+                if target
+                    .openjdk_versions()
+                    .is_some_and(|versions| !versions.contains(&version))
+                {
+                    return Err(unavailable(language, target));
+                }
+                openjdk_plan(version, target.package_manager())
+            }
+            Some(other) => return Err(unsupported_option(language, other)),
+        };
+
+        for tool in &language.options.tools {
+            let (name, tool_version) = match tool.split_once('@') {
+                Some((name, tool_version)) => (name, tool_version),
+                None => (tool.as_str(), ""),
+            };
+            if !tool_version.is_empty() && !is_numeric_version(tool_version) {
+                return Err(unsupported_option(language, tool));
+            }
+
+            plan = match name {
+                "maven" => maven(plan, non_empty_or(tool_version, MAVEN_VERSION)),
+                "gradle" => gradle(plan, non_empty_or(tool_version, GRADLE_VERSION)),
+                _ => return Err(unsupported_option(language, tool)),
+            };
+        }
+
+        Ok(plan)
+    }
+}
+
+/// Eclipse Temurin builds from the Adoptium API, verified against the
+/// checksum published next to the release asset
+fn temurin_plan(version: &str, target: &TargetOs) -> InstallPlan {
+    let os = if target.package_manager() == PackageManager::Apk {
+        "alpine-linux"
+    } else {
+        "linux"
+    };
+
+    InstallPlan::packages(&["ca-certificates", "curl", "tar", "gzip"])
+        .step(
+            "case \"$(uname -m)\" in x86_64) JDK_ARCH=x64 ;; aarch64) JDK_ARCH=aarch64 ;; \
+             *) echo \"Unsupported architecture: $(uname -m)\" >&2; exit 1 ;; esac",
+        )
+        .step(format!(
+            "JDK_URL=$(curl -fsSL -o jdk.tar.gz -w '%{{url_effective}}' \
+             https://api.adoptium.net/v3/binary/latest/{}/ga/{}/${{JDK_ARCH}}/jdk/hotspot/normal/eclipse)",
+            version, os
+        ))
+        .step(
+            "echo \"$(curl -fsSL \"${JDK_URL}.sha256.txt\" | cut -d ' ' -f 1)  jdk.tar.gz\" | sha256sum -c -",
+        )
+        .step(format!(
+            "mkdir -p {} && tar -xzf jdk.tar.gz -C {} --strip-components=1",
+            JAVA_HOME, JAVA_HOME
+        ))
+        .step("rm jdk.tar.gz")
+        .env("JAVA_HOME", JAVA_HOME)
+        .env("PATH", format!("{}/bin:${{PATH}}", JAVA_HOME))
+}
+
+/// The distribution's OpenJDK packages, with a stable JAVA_HOME symlink since
+/// every distro installs the JDK under a differently named directory
+fn openjdk_plan(version: &str, package_manager: PackageManager) -> InstallPlan {
+    let package = match package_manager {
+        PackageManager::Apt => format!("openjdk-{}-jdk-headless", version),
+        PackageManager::Apk => format!("openjdk{}-jdk", version),
+        PackageManager::Dnf | PackageManager::Zypper => {
+            format!("java-{}-openjdk-devel", version)
+        }
+        PackageManager::Pacman => format!("jdk{}-openjdk", version),
+    };
+
+    InstallPlan::packages(&[package])
+        .step(format!(
+            "ln -s \"$(dirname \"$(dirname \"$(readlink -f \"$(command -v javac)\")\")\")\" {}",
+            JAVA_HOME
+        ))
+        .env("JAVA_HOME", JAVA_HOME)
+}
+
+fn maven(plan: InstallPlan, version: &str) -> InstallPlan {
+    let url = format!(
+        "https://archive.apache.org/dist/maven/maven-3/{}/binaries/apache-maven-{}-bin.tar.gz",
+        version, version
+    );
+
+    add_packages(plan, &["ca-certificates", "curl", "tar", "gzip"])
+        .step(format!("curl -fsSLo maven.tar.gz {}", url))
+        .step(format!(
+            "echo \"$(curl -fsSL {}.sha512 | cut -d ' ' -f 1)  maven.tar.gz\" | sha512sum -c -",
+            url
+        ))
+        .step("mkdir -p /opt/maven && tar -xzf maven.tar.gz -C /opt/maven --strip-components=1")
+        .step("rm maven.tar.gz")
+        .env("MAVEN_HOME", "/opt/maven")
+        .env("PATH", "/opt/maven/bin:${PATH}")
+}
+
+fn gradle(plan: InstallPlan, version: &str) -> InstallPlan {
+    let url = format!(
+        "https://services.gradle.org/distributions/gradle-{}-bin.zip",
+        version
+    );
+
+    add_packages(plan, &["ca-certificates", "curl", "unzip"])
+        .step(format!("curl -fsSLo gradle.zip {}", url))
+        .step(format!(
+            "echo \"$(curl -fsSL {}.sha256)  gradle.zip\" | sha256sum -c -",
+            url
+        ))
+        .step("unzip -q gradle.zip -d /opt")
+        .step(format!("mv /opt/gradle-{} /opt/gradle", version))
+        .step("rm gradle.zip")
+        .env("GRADLE_HOME", "/opt/gradle")
+        .env("PATH", "/opt/gradle/bin:${PATH}")
+}
+
+fn add_packages(mut plan: InstallPlan, packages: &[&str]) -> InstallPlan {
+    for package in packages {
+        if !plan.packages.iter().any(|p| p == package) {
+            plan.packages.push(package.to_string());
+        }
+    }
+    plan
+}
+
+fn unsupported_option(language: &Language, value: &str) -> GeneratorError {
+    GeneratorError::UnsupportedOption {
+        language: language.name.clone(),
+        value: value.to_string(),
+    }
+}
+
+fn non_empty_or<'a>(value: &'a str, default: &'a str) -> &'a str {
+    if value.is_empty() {
+        default
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::config::LanguageOptions;
+    use crate::services::languages::testing::{language, language_with, render, target};
+
+    fn options(distribution: Option<&str>, tools: &[&str]) -> LanguageOptions {
+        LanguageOptions {
+            distribution: distribution.map(str::to_string),
+            tools: tools.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_java_defaults_to_temurin() {
+        let dockerfile = render(
+            &JavaInstaller,
+            &language("java", "21"),
+            &target("ubuntu", "24.04"),
+        );
+        assert!(dockerfile.contains("https://api.adoptium.net/v3/binary/latest/21/ga/linux/"));
+        assert!(dockerfile.contains("sha256sum -c -"));
+        assert!(dockerfile.contains("ENV JAVA_HOME=\"/opt/java\""));
+    }
+
+    #[test]
+    fn test_java_temurin_alpine() {
+        let dockerfile = render(
+            &JavaInstaller,
+            &language("java", "17"),
+            &target("alpine", "3.20"),
+        );
+        assert!(dockerfile.contains("/v3/binary/latest/17/ga/alpine-linux/"));
+        assert!(dockerfile.contains("apk add ca-certificates curl tar gzip"));
+    }
+
+    #[test]
+    fn test_java_openjdk_packages() {
+        for (os_type, version, package) in [
+            ("debian", "bookworm", "openjdk-17-jdk-headless"),
+            ("alpine", "3.20", "openjdk17-jdk"),
+            ("fedora", "40", "java-17-openjdk-devel"),
+            ("opensuse", "15.6", "java-17-openjdk-devel"),
+            ("arch", "latest", "jdk17-openjdk"),
+        ] {
+            let dockerfile = render(
+                &JavaInstaller,
+                &language_with("java", "17", options(Some("openjdk"), &[])),
+                &target(os_type, version),
+            );
+            assert!(dockerfile.contains(package), "{}", dockerfile);
+            assert!(dockerfile.contains("ENV JAVA_HOME=\"/opt/java\""));
+            assert!(!dockerfile.contains("adoptium"));
+        }
+    }
+
+    #[test]
+    fn test_java_openjdk_unavailable_version() {
+        for (os_type, version) in [
+            ("debian", "bookworm"),
+            ("ubuntu", "focal"),
+            ("alpine", "3.18"),
+        ] {
+            let err = JavaInstaller
+                .install_plan(
+                    &language_with("java", "21", options(Some("openjdk"), &[])),
+                    &target(os_type, version),
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("java 21 is not available on {} {}", os_type, version)
+            );
+        }
+
+        // Temurin builds are not tied to the distribution's packages
+        assert!(JavaInstaller
+            .install_plan(&language("java", "21"), &target("debian", "bookworm"))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_java_build_tools() {
+        let dockerfile = render(
+            &JavaInstaller,
+            &language_with("java", "21", options(None, &["maven", "gradle@8.5"])),
+            &target("debian", "bookworm"),
+        );
+        assert!(dockerfile.contains("apache-maven-3.9.9-bin.tar.gz"));
+        assert!(dockerfile.contains("sha512sum -c -"));
+        assert!(dockerfile.contains("gradle-8.5-bin.zip"));
+        assert!(dockerfile.contains("ENV MAVEN_HOME=\"/opt/maven\""));
+        assert!(dockerfile.contains("ENV GRADLE_HOME=\"/opt/gradle\""));
+        assert!(dockerfile.contains(
+            "apt-get install -y --no-install-recommends ca-certificates curl tar gzip unzip"
+        ));
+    }
+
+    #[test]
+    fn test_java_rejects_unknown_options() {
+        let target = target("ubuntu", "22.04");
+        for options in [
+            options(Some("graalvm"), &[]),
+            options(None, &["ant"]),
+            options(None, &["maven@latest"]),
+        ] {
+            let err = JavaInstaller
+                .install_plan(&language_with("java", "21", options), &target)
+                .unwrap_err();
+            assert!(matches!(err, GeneratorError::UnsupportedOption { .. }));
+        }
+
+        assert!(JavaInstaller
+            .install_plan(&language("java", "21-ea"), &target)
+            .is_err());
+    }
+}
@@ -0,0 +1,166 @@
+//! Language toolchain installers used by the Dockerfile generator.
+//!
+//! Each toolchain lives in its own module and implements [`LanguageInstaller`].
+//! The generator looks installers up by language name through a
+//! [`LanguageRegistry`], so adding a toolchain only requires registering it here.
+
+mod go;
+mod java;
+mod nodejs;
+mod perl;
+mod php;
+mod python;
+mod ruby;
+mod rust;
+
+use crate::models::config::Language;
+use crate::services::distro::TargetOs;
+use crate::services::dockerfile_generator::GeneratorError;
+use crate::services::package_manager::InstallPlan;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+pub trait LanguageInstaller: Send + Sync {
+    /// Name used in `Language.name` to select this installer
+    fn name(&self) -> &'static str;
+
+    /// Packages, setup steps and environment needed for the requested version
+    fn install_plan(
+        &self,
+        language: &Language,
+        target: &TargetOs,
+    ) -> Result<InstallPlan, GeneratorError>;
+
+    /// Directories the toolchain downloads dependencies into, mounted as
+    /// BuildKit caches into its own install step and the build step of
+    /// cached multi-stage builds
+    fn cache_dirs(&self) -> &'static [&'static str] {
+        &[]
+    }
+}
+
+pub struct LanguageRegistry {
+    installers: HashMap<&'static str, Box<dyn LanguageInstaller>>,
+}
+
+impl LanguageRegistry {
+    pub fn new() -> Self {
+        Self {
+            installers: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, installer: impl LanguageInstaller + 'static) {
+        self.installers
+            .insert(installer.name(), Box::new(installer));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn LanguageInstaller> {
+        self.installers
+            .get(name)
+            .map(|installer| installer.as_ref())
+    }
+}
+
+/// True for versions made only of dot-separated numbers, e.g. `3`, `3.12` or `3.12.1`
+pub(crate) fn is_numeric_version(version: &str) -> bool {
+    version
+        .split('.')
+        .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Error for a language version the target OS cannot provide
+pub(crate) fn unavailable(language: &Language, target: &TargetOs) -> GeneratorError {
+    GeneratorError::UnavailableVersion {
+        language: language.name.clone(),
+        version: language.version.clone(),
+        os: target.describe(),
+    }
+}
+
+impl Default for LanguageRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(python::PythonInstaller);
+        registry.register(nodejs::NodeJsInstaller);
+        registry.register(rust::RustInstaller);
+        registry.register(go::GoInstaller);
+        registry.register(java::JavaInstaller);
+        registry.register(ruby::RubyInstaller);
+        registry.register(php::PhpInstaller);
+        registry.register(perl::PerlInstaller);
+        registry
+    }
+}
+
+/// Registry with all built-in installers
+pub fn registry() -> &'static LanguageRegistry {
+    static REGISTRY: OnceLock<LanguageRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(LanguageRegistry::default)
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+    use crate::models::config::{LanguageOptions, OsConfig};
+
+    pub fn language(name: &str, version: &str) -> Language {
+        Language {
+            name: name.to_string(),
+            version: version.to_string(),
+            options: LanguageOptions::default(),
+        }
+    }
+
+    pub fn language_with(name: &str, version: &str, options: LanguageOptions) -> Language {
+        Language {
+            options,
+            ..language(name, version)
+        }
+    }
+
+    pub fn target(os_type: &str, version: &str) -> TargetOs {
+        TargetOs::from_config(&OsConfig {
+            os_type: os_type.to_string(),
+            version: version.to_string(),
+        })
+        .unwrap()
+    }
+
+    /// Render an installer's plan for the given OS as Dockerfile text
+    pub fn render(
+        installer: &dyn LanguageInstaller,
+        language: &Language,
+        target: &TargetOs,
+    ) -> String {
+        let instructions: Vec<String> = installer
+            .install_plan(language, target)
+            .unwrap()
+            .instructions(target.package_manager(), false)
+            .iter()
+            .map(|i| i.to_string())
+            .collect();
+        instructions.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_contains_builtin_languages() {
+        let registry = registry();
+        for name in [
+            "python", "nodejs", "rust", "go", "java", "ruby", "php", "perl",
+        ] {
+            let installer = registry.get(name).expect("missing installer");
+            assert_eq!(installer.name(), name);
+        }
+    }
+
+    #[test]
+    fn test_registry_unknown_language() {
+        assert!(registry().get("cobol").is_none());
+    }
+}
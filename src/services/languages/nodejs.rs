@@ -0,0 +1,217 @@
+use super::{is_numeric_version, unavailable, LanguageInstaller};
+use crate::models::config::Language;
+use crate::services::distro::TargetOs;
+use crate::services::dockerfile_generator::GeneratorError;
+use crate::services::package_manager::{InstallPlan, PackageManager};
+
+pub struct NodeJsInstaller;
+
+impl LanguageInstaller for NodeJsInstaller {
+    fn name(&self) -> &'static str {
+        "nodejs"
+    }
+
+    fn cache_dirs(&self) -> &'static [&'static str] {
+        &["/root/.npm"]
+    }
+
+    fn install_plan(
+        &self,
+        language: &Language,
+        target: &TargetOs,
+    ) -> Result<InstallPlan, GeneratorError> {
+        let version = &language.version;
+        if !is_numeric_version(version) {
+            return Err(unavailable(language, target));
+        }
+        let package_manager = target.package_manager();
+
+        let plan = match package_manager {
+            PackageManager::Apt => InstallPlan::packages(&["ca-certificates", "curl"])
+                .step(format!(
+                    "curl -fsSL https://deb.nodesource.com/setup_{}.x -o nodesource_setup.sh",
+                    version
+                ))
+                .step("bash nodesource_setup.sh")
+                .step(package_manager.install(&["nodejs"]))
+                .step("rm nodesource_setup.sh"),
+            PackageManager::Apk => alpine_plan(language, target)?,
+            // curl ships with the Fedora and Rocky base images (as curl-minimal on Rocky)
+            PackageManager::Dnf => InstallPlan::default()
+                .step(format!(
+                    "curl -fsSL https://rpm.nodesource.com/setup_{}.x -o nodesource_setup.sh",
+                    version
+                ))
+                .step("bash nodesource_setup.sh")
+                .step(package_manager.install(&["nodejs"]))
+                .step("rm nodesource_setup.sh")
+                .step(package_manager.cleanup()),
+            PackageManager::Zypper => {
+                InstallPlan::packages(&[format!("nodejs{}", version), format!("npm{}", version)])
+            }
+            PackageManager::Pacman => InstallPlan::packages(&[arch_nodejs_package(version), "npm"]),
+        };
+
+        Ok(plan)
+    }
+}
+
+const MUSL_BUILDS_URL: &str = "https://unofficial-builds.nodejs.org/download/release";
+
+/// Alpine only packages one or two Node.js majors per release. Prefer the
+/// release package pinned to the requested major and fall back to the
+/// unofficial musl builds for other majors or exact versions.
+fn alpine_plan(language: &Language, target: &TargetOs) -> Result<InstallPlan, GeneratorError> {
+    let version = language.version.as_str();
+    let release = target.alpine_release().expect("validated alpine release");
+    let package = if release.nodejs == version {
+        Some("nodejs")
+    } else if release.nodejs_current == version {
+        Some("nodejs-current")
+    } else {
+        None
+    };
+
+    if let Some(package) = package {
+        return Ok(InstallPlan::packages(&[
+            format!("{}~{}", package, version),
+            "npm".to_string(),
+        ]));
+    }
+
+    // An exact version is used as-is, a bare major resolves to its newest release
+    let resolve_version = if version.contains('.') {
+        format!("NODE_VERSION=v{}", version)
+    } else {
+        format!(
+            "NODE_VERSION=$(curl -fsSL {}/index.json | grep -o '\"version\":\"v{}\\.[0-9.]*\"' | head -n 1 | cut -d '\"' -f 4)",
+            MUSL_BUILDS_URL, version
+        )
+    };
+    let major = version.split('.').next().unwrap_or(version);
+    let tarball = "node-${NODE_VERSION}-linux-x64-musl.tar.xz";
+
+    Ok(InstallPlan::packages(&["curl", "libstdc++", "xz"])
+        .step(format!(
+            "[ \"$(uname -m)\" = \"x86_64\" ] || (echo \"Node.js {} musl builds are only published for x86_64\" >&2 && exit 1)",
+            version
+        ))
+        .step(resolve_version)
+        .step(format!("curl -fsSLO {}/${{NODE_VERSION}}/{}", MUSL_BUILDS_URL, tarball))
+        .step(format!(
+            "curl -fsSL {}/${{NODE_VERSION}}/SHASUMS256.txt | grep \" {}$\" | sha256sum -c -",
+            MUSL_BUILDS_URL, tarball
+        ))
+        .step(format!(
+            "tar -xJf {} -C /usr/local --strip-components=1 --no-same-owner",
+            tarball
+        ))
+        .step(format!("rm {}", tarball))
+        .step(format!("node --version | grep -q '^v{}\\.'", major)))
+}
+
+/// Arch packages LTS releases under their codename and the current release as `nodejs`
+fn arch_nodejs_package(version: &str) -> &'static str {
+    match version {
+        "18" => "nodejs-lts-hydrogen",
+        "20" => "nodejs-lts-iron",
+        "22" => "nodejs-lts-jod",
+        _ => "nodejs",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::languages::testing::{language, render, target};
+
+    #[test]
+    fn test_nodejs_debian_uses_nodesource() {
+        let dockerfile = render(
+            &NodeJsInstaller,
+            &language("nodejs", "20"),
+            &target("debian", "bookworm"),
+        );
+        assert!(dockerfile.contains("https://deb.nodesource.com/setup_20.x"));
+        assert!(dockerfile.contains("apt-get install -y --no-install-recommends nodejs"));
+    }
+
+    #[test]
+    fn test_nodejs_alpine() {
+        let dockerfile = render(
+            &NodeJsInstaller,
+            &language("nodejs", "20"),
+            &target("alpine", "3.20"),
+        );
+        assert!(dockerfile.contains("apk add nodejs~20 npm"));
+    }
+
+    #[test]
+    fn test_nodejs_alpine_current_package() {
+        let dockerfile = render(
+            &NodeJsInstaller,
+            &language("nodejs", "22"),
+            &target("alpine", "3.20"),
+        );
+        assert!(dockerfile.contains("apk add nodejs-current~22 npm"));
+    }
+
+    #[test]
+    fn test_nodejs_alpine_falls_back_to_musl_build() {
+        let dockerfile = render(
+            &NodeJsInstaller,
+            &language("nodejs", "18"),
+            &target("alpine", "3.21"),
+        );
+        assert!(
+            dockerfile.contains("https://unofficial-builds.nodejs.org/download/release/index.json")
+        );
+        assert!(dockerfile.contains("v18\\.[0-9.]*"));
+        assert!(dockerfile.contains("sha256sum -c -"));
+        assert!(dockerfile.contains("node --version | grep -q '^v18\\.'"));
+        assert!(!dockerfile.contains("apk add nodejs"));
+    }
+
+    #[test]
+    fn test_nodejs_alpine_exact_version() {
+        let dockerfile = render(
+            &NodeJsInstaller,
+            &language("nodejs", "20.11.1"),
+            &target("alpine", "3.20"),
+        );
+        assert!(dockerfile.contains("NODE_VERSION=v20.11.1"));
+        assert!(dockerfile.contains("node-${NODE_VERSION}-linux-x64-musl.tar.xz"));
+    }
+
+    #[test]
+    fn test_nodejs_alpine_rejects_non_numeric_version() {
+        let err = NodeJsInstaller
+            .install_plan(&language("nodejs", "lts"), &target("alpine", "3.21"))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "nodejs lts is not available on alpine 3.21"
+        );
+    }
+
+    #[test]
+    fn test_nodejs_rocky_uses_rpm_nodesource() {
+        let dockerfile = render(
+            &NodeJsInstaller,
+            &language("nodejs", "22"),
+            &target("rocky", "9"),
+        );
+        assert!(dockerfile.contains("https://rpm.nodesource.com/setup_22.x"));
+        assert!(dockerfile.contains("dnf install -y nodejs"));
+    }
+
+    #[test]
+    fn test_nodejs_arch_lts_package() {
+        let dockerfile = render(
+            &NodeJsInstaller,
+            &language("nodejs", "20"),
+            &target("arch", "latest"),
+        );
+        assert!(dockerfile.contains("pacman -S --noconfirm --needed nodejs-lts-iron npm"));
+    }
+}
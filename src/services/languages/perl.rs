@@ -0,0 +1,133 @@
+use super::{is_numeric_version, unavailable, LanguageInstaller};
+use crate::models::config::Language;
+use crate::services::distro::{Runtime, TargetOs};
+use crate::services::dockerfile_generator::GeneratorError;
+use crate::services::package_manager::{InstallPlan, PackageManager};
+
+pub struct PerlInstaller;
+
+const CPAN_SOURCE_URL: &str = "https://www.cpan.org/src/5.0";
+
+impl LanguageInstaller for PerlInstaller {
+    fn name(&self) -> &'static str {
+        "perl"
+    }
+
+    fn install_plan(
+        &self,
+        language: &Language,
+        target: &TargetOs,
+    ) -> Result<InstallPlan, GeneratorError> {
+        let version = language.version.as_str();
+        let parts = version.split('.').count();
+        if !version.starts_with("5.") || !(2..=3).contains(&parts) || !is_numeric_version(version) {
+            return Err(unavailable(language, target));
+        }
+
+        let package_manager = target.package_manager();
+        if target.packaged_version(Runtime::Perl) == Some(version) {
+            let mut packages = match package_manager {
+                PackageManager::Apk => vec![
+                    format!("perl~{}", version),
+                    "perl-dev".to_string(),
+                    "perl-app-cpanminus".to_string(),
+                    "make".to_string(),
+                ],
+                _ => vec!["perl".to_string(), "cpanminus".to_string()],
+            };
+            packages.extend(
+                package_manager
+                    .build_essentials()
+                    .iter()
+                    .map(|p| p.to_string()),
+            );
+            return Ok(InstallPlan::packages(&packages));
+        }
+
+        Ok(source_plan(version, package_manager))
+    }
+}
+
+/// Build the requested perl from the CPAN source tarball and bootstrap cpanm.
+/// A minor version resolves to its newest maintenance release.
+fn source_plan(version: &str, package_manager: PackageManager) -> InstallPlan {
+    let mut packages = vec!["ca-certificates", "curl", "tar", "gzip", "make"];
+    packages.extend(package_manager.build_essentials());
+
+    let resolve_tarball = if version.split('.').count() == 3 {
+        format!("PERL_TARBALL=perl-{}.tar.gz", version)
+    } else {
+        format!(
+            "PERL_TARBALL=$(curl -fsSL {}/ | grep -o 'perl-{}\\.[0-9]*\\.tar\\.gz' | sort -u -t . -k 3,3n | tail -n 1)",
+            CPAN_SOURCE_URL,
+            version.replace('.', "\\.")
+        )
+    };
+
+    InstallPlan::packages(&packages)
+        .step(resolve_tarball)
+        .step(format!("curl -fsSLO {}/${{PERL_TARBALL}}", CPAN_SOURCE_URL))
+        .step(format!(
+            "echo \"$(curl -fsSL {}/${{PERL_TARBALL}}.sha256.txt)  ${{PERL_TARBALL}}\" | sha256sum -c -",
+            CPAN_SOURCE_URL
+        ))
+        .step("mkdir perl-src && tar -xzf \"${PERL_TARBALL}\" -C perl-src --strip-components=1")
+        .step("(cd perl-src && ./Configure -des -Dprefix=/usr/local -Dman1dir=none -Dman3dir=none && make -j\"$(nproc)\" && make install)")
+        .step("rm -rf perl-src \"${PERL_TARBALL}\"")
+        .step("curl -fsSLo cpanm https://cpanmin.us")
+        .step("/usr/local/bin/perl cpanm --notest App::cpanminus")
+        .step("rm cpanm")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::languages::testing::{language, render, target};
+
+    #[test]
+    fn test_perl_distro_packages() {
+        let dockerfile = render(
+            &PerlInstaller,
+            &language("perl", "5.36"),
+            &target("debian", "bookworm"),
+        );
+        assert!(dockerfile
+            .contains("apt-get install -y --no-install-recommends perl cpanminus build-essential"));
+
+        let alpine = render(
+            &PerlInstaller,
+            &language("perl", "5.38"),
+            &target("alpine", "3.20"),
+        );
+        assert!(alpine.contains("apk add perl~5.38 perl-dev perl-app-cpanminus make gcc musl-dev"));
+    }
+
+    #[test]
+    fn test_perl_source_build() {
+        let dockerfile = render(
+            &PerlInstaller,
+            &language("perl", "5.40"),
+            &target("ubuntu", "22.04"),
+        );
+        assert!(dockerfile.contains("grep -o 'perl-5\\.40\\.[0-9]*\\.tar\\.gz'"));
+        assert!(dockerfile.contains("sha256sum -c -"));
+        assert!(dockerfile.contains("./Configure -des -Dprefix=/usr/local"));
+        assert!(dockerfile.contains("perl cpanm --notest App::cpanminus"));
+
+        let exact = render(
+            &PerlInstaller,
+            &language("perl", "5.38.2"),
+            &target("fedora", "40"),
+        );
+        assert!(exact.contains("PERL_TARBALL=perl-5.38.2.tar.gz"));
+    }
+
+    #[test]
+    fn test_perl_rejects_invalid_versions() {
+        for version in ["6.0", "5", "5.x", "latest"] {
+            assert!(PerlInstaller
+                .install_plan(&language("perl", version), &target("debian", "bookworm"))
+                .is_err());
+        }
+    }
+}
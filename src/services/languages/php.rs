@@ -0,0 +1,238 @@
+use super::{is_numeric_version, unavailable, LanguageInstaller};
+use crate::models::config::Language;
+use crate::services::distro::{Distro, TargetOs};
+use crate::services::dockerfile_generator::GeneratorError;
+use crate::services::package_manager::InstallPlan;
+
+pub struct PhpInstaller;
+
+/// Extensions installed when the config does not list any
+const DEFAULT_EXTENSIONS: &[&str] = &["curl", "mbstring", "xml", "zip", "intl"];
+
+impl LanguageInstaller for PhpInstaller {
+    fn name(&self) -> &'static str {
+        "php"
+    }
+
+    fn install_plan(
+        &self,
+        language: &Language,
+        target: &TargetOs,
+    ) -> Result<InstallPlan, GeneratorError> {
+        // PHP packages are versioned per minor release, e.g. php8.3-cli
+        let version = language.version.as_str();
+        if version.split('.').count() != 2 || !is_numeric_version(version) {
+            return Err(unavailable(language, target));
+        }
+
+        let mut extensions: Vec<&str> = if language.options.extensions.is_empty() {
+            DEFAULT_EXTENSIONS.to_vec()
+        } else {
+            language
+                .options
+                .extensions
+                .iter()
+                .map(String::as_str)
+                .collect()
+        };
+        if let Some(invalid) = extensions.iter().find(|e| {
+            e.is_empty()
+                || !e
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        }) {
+            return Err(GeneratorError::UnsupportedOption {
+                language: language.name.clone(),
+                value: invalid.to_string(),
+            });
+        }
+
+        let package_manager = target.package_manager();
+        let plan = match target.distro {
+            Distro::Ubuntu => {
+                let packages = apt_packages(version, &extensions);
+                InstallPlan::packages(&[
+                    "software-properties-common",
+                    "ca-certificates",
+                    "curl",
+                    "unzip",
+                ])
+                .step("add-apt-repository ppa:ondrej/php -y")
+                .step(package_manager.update())
+                .step(package_manager.install(&packages))
+            }
+            Distro::Debian => {
+                let packages = apt_packages(version, &extensions);
+                InstallPlan::packages(&["ca-certificates", "curl", "unzip"])
+                    .step("curl -fsSLo /tmp/debsuryorg-archive-keyring.deb https://packages.sury.org/debsuryorg-archive-keyring.deb")
+                    .step("dpkg -i /tmp/debsuryorg-archive-keyring.deb")
+                    .step("rm /tmp/debsuryorg-archive-keyring.deb")
+                    .step("echo \"deb [signed-by=/usr/share/keyrings/deb.sury.org-php.gpg] https://packages.sury.org/php/ $(. /etc/os-release && echo \"$VERSION_CODENAME\") main\" > /etc/apt/sources.list.d/php.list")
+                    .step(package_manager.update())
+                    .step(package_manager.install(&packages))
+            }
+            Distro::Alpine => {
+                let release = target.alpine_release().expect("validated alpine release");
+                if !release.php.contains(&version) {
+                    return Err(unavailable(language, target));
+                }
+
+                // Composer needs phar, openssl and iconv, which Alpine splits out
+                for required in ["phar", "openssl", "iconv"] {
+                    if !extensions.contains(&required) {
+                        extensions.push(required);
+                    }
+                }
+                let prefix = format!("php{}", version.replace('.', ""));
+                let mut packages = vec![prefix.clone()];
+                packages.extend(extensions.iter().map(|e| format!("{}-{}", prefix, e)));
+                packages.extend(["curl".to_string(), "unzip".to_string()]);
+
+                InstallPlan::packages(&packages).step(format!(
+                    "[ -e /usr/bin/php ] || ln -s /usr/bin/{} /usr/bin/php",
+                    prefix
+                ))
+            }
+            // Other distributions only ship a single PHP release without
+            // versioned package names
+            _ => return Err(unavailable(language, target)),
+        };
+
+        Ok(plan
+            .step("curl -fsSLo composer-setup.php https://getcomposer.org/installer")
+            .step("[ \"$(php -r \"echo hash_file('sha384', 'composer-setup.php');\")\" = \"$(curl -fsSL https://composer.github.io/installer.sig)\" ]")
+            .step("php composer-setup.php --quiet --install-dir=/usr/local/bin --filename=composer")
+            .step("rm composer-setup.php"))
+    }
+}
+
+fn apt_packages(version: &str, extensions: &[&str]) -> Vec<String> {
+    let mut packages = vec![format!("php{}-cli", version)];
+    for extension in extensions {
+        let package = format!("php{}-{}", version, apt_extension_package(extension));
+        if !packages.contains(&package) {
+            packages.push(package);
+        }
+    }
+    packages
+}
+
+/// The Debian packaging names extensions after the library they bind to, so
+/// e.g. `pdo_pgsql` and `pgsql` both come from `phpX.Y-pgsql`
+fn apt_extension_package(extension: &str) -> &str {
+    match extension {
+        "pdo_pgsql" => "pgsql",
+        "pdo_mysql" | "mysqli" | "mysqlnd" => "mysql",
+        "pdo_sqlite" => "sqlite3",
+        "pdo_odbc" => "odbc",
+        "pdo_dblib" => "sybase",
+        "pdo_firebird" => "interbase",
+        "pdo" => "common",
+        extension => extension,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::config::LanguageOptions;
+    use crate::services::languages::testing::{language, language_with, render, target};
+
+    #[test]
+    fn test_php_ubuntu_uses_ondrej_ppa() {
+        let dockerfile = render(
+            &PhpInstaller,
+            &language("php", "8.3"),
+            &target("ubuntu", "22.04"),
+        );
+        assert!(dockerfile.contains("add-apt-repository ppa:ondrej/php -y"));
+        assert!(dockerfile.contains(
+            "apt-get install -y --no-install-recommends php8.3-cli php8.3-curl php8.3-mbstring php8.3-xml php8.3-zip php8.3-intl"
+        ));
+        assert!(dockerfile.contains("--filename=composer"));
+        assert!(dockerfile.contains("hash_file('sha384', 'composer-setup.php')"));
+    }
+
+    #[test]
+    fn test_php_debian_uses_sury_repository() {
+        let dockerfile = render(
+            &PhpInstaller,
+            &language("php", "8.2"),
+            &target("debian", "bookworm"),
+        );
+        assert!(dockerfile.contains("https://packages.sury.org/php/"));
+        assert!(dockerfile.contains("php8.2-cli"));
+        assert!(!dockerfile.contains("add-apt-repository"));
+    }
+
+    #[test]
+    fn test_php_apt_extension_packages() {
+        let options = LanguageOptions {
+            extensions: [
+                "pdo_pgsql",
+                "pgsql",
+                "pdo_mysql",
+                "mysqli",
+                "pdo_sqlite",
+                "gd",
+            ]
+            .iter()
+            .map(|e| e.to_string())
+            .collect(),
+            ..Default::default()
+        };
+        let dockerfile = render(
+            &PhpInstaller,
+            &language_with("php", "8.3", options),
+            &target("debian", "bookworm"),
+        );
+        assert!(dockerfile.contains(
+            "apt-get install -y --no-install-recommends php8.3-cli php8.3-pgsql php8.3-mysql php8.3-sqlite3 php8.3-gd"
+        ));
+        assert!(!dockerfile.contains("pdo_"));
+    }
+
+    #[test]
+    fn test_php_alpine_packages() {
+        let options = LanguageOptions {
+            extensions: vec!["pdo_pgsql".to_string()],
+            ..Default::default()
+        };
+        let dockerfile = render(
+            &PhpInstaller,
+            &language_with("php", "8.3", options),
+            &target("alpine", "3.20"),
+        );
+        assert!(dockerfile.contains(
+            "apk add php83 php83-pdo_pgsql php83-phar php83-openssl php83-iconv curl unzip"
+        ));
+        assert!(dockerfile.contains("ln -s /usr/bin/php83 /usr/bin/php"));
+
+        let err = PhpInstaller
+            .install_plan(&language("php", "8.1"), &target("alpine", "3.21"))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "php 8.1 is not available on alpine 3.21");
+    }
+
+    #[test]
+    fn test_php_rejects_invalid_input() {
+        assert!(PhpInstaller
+            .install_plan(&language("php", "8"), &target("ubuntu", "22.04"))
+            .is_err());
+        assert!(PhpInstaller
+            .install_plan(&language("php", "8.3"), &target("fedora", "40"))
+            .is_err());
+
+        let options = LanguageOptions {
+            extensions: vec!["gd; rm -rf /".to_string()],
+            ..Default::default()
+        };
+        let err = PhpInstaller
+            .install_plan(
+                &language_with("php", "8.3", options),
+                &target("ubuntu", "22.04"),
+            )
+            .unwrap_err();
+        assert!(matches!(err, GeneratorError::UnsupportedOption { .. }));
+    }
+}
@@ -0,0 +1,243 @@
+use super::{is_numeric_version, unavailable, LanguageInstaller};
+use crate::models::config::Language;
+use crate::services::distro::{Distro, Runtime, TargetOs};
+use crate::services::dockerfile_generator::GeneratorError;
+use crate::services::package_manager::{InstallPlan, PackageManager};
+
+pub struct PythonInstaller;
+
+/// uv release used to provision interpreters the distribution does not ship
+const UV_VERSION: &str = "0.5.11";
+const UV_PYTHON_DIR: &str = "/opt/python";
+
+/// How the requested interpreter is provided on a given base image
+#[derive(Debug, PartialEq, Eq)]
+enum Strategy {
+    /// The distribution's own `python3` is the requested version
+    Distro,
+    /// Versioned packages from the deadsnakes PPA (Ubuntu only)
+    Deadsnakes,
+    /// Versioned packages from the distribution repositories
+    VersionedPackages,
+    /// A standalone interpreter managed by uv
+    Uv,
+}
+
+fn strategy(version: &str, target: &TargetOs) -> Strategy {
+    match target.distro {
+        Distro::Ubuntu => Strategy::Deadsnakes,
+        Distro::Debian => match target.packaged_version(Runtime::Python) {
+            Some(packaged) if packaged == version => Strategy::Distro,
+            _ => Strategy::Uv,
+        },
+        Distro::Alpine => Strategy::Distro,
+        Distro::Fedora | Distro::Rocky | Distro::OpenSuse => Strategy::VersionedPackages,
+        // Arch is a rolling release and only ships the current interpreter
+        Distro::Arch => Strategy::Uv,
+    }
+}
+
+impl LanguageInstaller for PythonInstaller {
+    fn name(&self) -> &'static str {
+        "python"
+    }
+
+    fn cache_dirs(&self) -> &'static [&'static str] {
+        &["/root/.cache/pip"]
+    }
+
+    fn install_plan(
+        &self,
+        language: &Language,
+        target: &TargetOs,
+    ) -> Result<InstallPlan, GeneratorError> {
+        let version = &language.version;
+        if !version.starts_with("3.") || !is_numeric_version(version) {
+            return Err(unavailable(language, target));
+        }
+        let package_manager = target.package_manager();
+
+        let plan = match (strategy(version, target), package_manager) {
+            (Strategy::Deadsnakes, _) => InstallPlan::packages(&["software-properties-common"])
+                .step("add-apt-repository ppa:deadsnakes/ppa -y")
+                .step(package_manager.update())
+                .step(package_manager.install(&[
+                    format!("python{}", version),
+                    format!("python{}-pip", version),
+                ])),
+            (Strategy::Distro, PackageManager::Apk) => {
+                let release = target.alpine_release().expect("validated alpine release");
+                if release.python != version {
+                    return Err(unavailable(language, target));
+                }
+                InstallPlan::packages(&[
+                    format!("python3~{}", release.python),
+                    "py3-pip".to_string(),
+                ])
+            }
+            (Strategy::Distro, _) => {
+                InstallPlan::packages(&["python3", "python3-pip", "python3-venv"])
+            }
+            (Strategy::VersionedPackages, PackageManager::Zypper) => {
+                let suffix = version.replace('.', "");
+                InstallPlan::packages(&[
+                    format!("python{}", suffix),
+                    format!("python{}-pip", suffix),
+                ])
+            }
+            (Strategy::VersionedPackages, _) => {
+                InstallPlan::packages(&[format!("python{}", version)])
+                    .step(format!("python{} -m ensurepip --upgrade", version))
+            }
+            (Strategy::Uv, _) => uv_plan(version),
+        };
+
+        Ok(plan)
+    }
+}
+
+/// Install uv and let it download a standalone build of the requested interpreter
+fn uv_plan(version: &str) -> InstallPlan {
+    InstallPlan::packages(&["ca-certificates", "curl"])
+        .step(format!(
+            "curl -LsSf https://astral.sh/uv/{}/install.sh -o uv-install.sh",
+            UV_VERSION
+        ))
+        .step("env UV_INSTALL_DIR=/usr/local/bin UV_NO_MODIFY_PATH=1 sh uv-install.sh")
+        .step("rm uv-install.sh")
+        .step(format!(
+            "UV_PYTHON_INSTALL_DIR={} uv python install {}",
+            UV_PYTHON_DIR, version
+        ))
+        .step(format!(
+            "ln -sf \"$(UV_PYTHON_INSTALL_DIR={} uv python find {})\" /usr/local/bin/python{}",
+            UV_PYTHON_DIR, version, version
+        ))
+        .step(format!("ln -sf python{} /usr/local/bin/python3", version))
+        .env("UV_PYTHON_INSTALL_DIR", UV_PYTHON_DIR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::languages::testing::{language, render, target};
+
+    #[test]
+    fn test_python_ubuntu_uses_deadsnakes() {
+        let dockerfile = render(
+            &PythonInstaller,
+            &language("python", "3.12"),
+            &target("ubuntu", "22.04"),
+        );
+        assert!(dockerfile.contains("ppa:deadsnakes/ppa"));
+        assert!(dockerfile.contains("python3.12 python3.12-pip"));
+    }
+
+    #[test]
+    fn test_python_alpine() {
+        let dockerfile = render(
+            &PythonInstaller,
+            &language("python", "3.12"),
+            &target("alpine", "latest"),
+        );
+        assert!(dockerfile.contains("apk add python3~3.12 py3-pip"));
+        assert!(!dockerfile.contains("apt-get"));
+    }
+
+    #[test]
+    fn test_python_alpine_follows_release() {
+        let dockerfile = render(
+            &PythonInstaller,
+            &language("python", "3.11"),
+            &target("alpine", "3.18"),
+        );
+        assert!(dockerfile.contains("apk add python3~3.11 py3-pip"));
+
+        let err = PythonInstaller
+            .install_plan(&language("python", "3.9"), &target("alpine", "3.20"))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "python 3.9 is not available on alpine 3.20"
+        );
+    }
+
+    #[test]
+    fn test_python_rpm_distros() {
+        let fedora = render(
+            &PythonInstaller,
+            &language("python", "3.12"),
+            &target("fedora", "40"),
+        );
+        assert!(fedora.contains("dnf install -y python3.12"));
+        assert!(fedora.contains("python3.12 -m ensurepip"));
+
+        let opensuse = render(
+            &PythonInstaller,
+            &language("python", "3.11"),
+            &target("opensuse", "15.6"),
+        );
+        assert!(opensuse.contains("zypper --non-interactive install python311 python311-pip"));
+    }
+
+    #[test]
+    fn test_python_debian_strategy_matrix() {
+        let cases = [
+            ("bullseye", "3.9", Strategy::Distro),
+            ("bullseye", "3.11", Strategy::Uv),
+            ("bookworm", "3.11", Strategy::Distro),
+            ("bookworm", "3.12", Strategy::Uv),
+            ("12", "3.11", Strategy::Distro),
+            ("trixie", "3.13", Strategy::Distro),
+            ("trixie", "3.10", Strategy::Uv),
+            ("sid", "3.12", Strategy::Uv),
+        ];
+
+        for (debian, python, expected) in cases {
+            assert_eq!(
+                strategy(python, &target("debian", debian)),
+                expected,
+                "debian {} python {}",
+                debian,
+                python
+            );
+        }
+    }
+
+    #[test]
+    fn test_python_debian_distro_packages() {
+        let dockerfile = render(
+            &PythonInstaller,
+            &language("python", "3.11"),
+            &target("debian", "bookworm"),
+        );
+        assert!(dockerfile.contains(
+            "apt-get install -y --no-install-recommends python3 python3-pip python3-venv"
+        ));
+        assert!(!dockerfile.contains("deadsnakes"));
+    }
+
+    #[test]
+    fn test_python_debian_uv_interpreter() {
+        let dockerfile = render(
+            &PythonInstaller,
+            &language("python", "3.12"),
+            &target("debian", "bookworm"),
+        );
+        assert!(!dockerfile.contains("add-apt-repository"));
+        assert!(dockerfile.contains("https://astral.sh/uv/"));
+        assert!(dockerfile.contains("uv python install 3.12"));
+        assert!(dockerfile.contains("/usr/local/bin/python3.12"));
+    }
+
+    #[test]
+    fn test_python_arch_uses_uv() {
+        let dockerfile = render(
+            &PythonInstaller,
+            &language("python", "3.10"),
+            &target("arch", "latest"),
+        );
+        assert!(dockerfile.contains("pacman -S --noconfirm --needed ca-certificates curl"));
+        assert!(dockerfile.contains("uv python install 3.10"));
+    }
+}
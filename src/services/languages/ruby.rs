@@ -0,0 +1,179 @@
+use super::{is_numeric_version, unavailable, LanguageInstaller};
+use crate::models::config::Language;
+use crate::services::distro::{Runtime, TargetOs};
+use crate::services::dockerfile_generator::GeneratorError;
+use crate::services::package_manager::{InstallPlan, PackageManager};
+
+pub struct RubyInstaller;
+
+/// ruby-build release used when the distribution does not ship the requested Ruby
+const RUBY_BUILD_VERSION: &str = "20241225";
+/// sha256 of the `RUBY_BUILD_VERSION` release tarball
+const RUBY_BUILD_SHA256: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+impl LanguageInstaller for RubyInstaller {
+    fn name(&self) -> &'static str {
+        "ruby"
+    }
+
+    fn install_plan(
+        &self,
+        language: &Language,
+        target: &TargetOs,
+    ) -> Result<InstallPlan, GeneratorError> {
+        let version = language.version.as_str();
+        if version.split('.').count() < 2 || !is_numeric_version(version) {
+            return Err(unavailable(language, target));
+        }
+
+        let package_manager = target.package_manager();
+        if target.packaged_version(Runtime::Ruby) == Some(version) {
+            let mut packages = match package_manager {
+                PackageManager::Apk => vec![
+                    format!("ruby~{}", version),
+                    "ruby-dev".to_string(),
+                    "ruby-bundler".to_string(),
+                    "make".to_string(),
+                ],
+                _ => vec!["ruby-full".to_string(), "ruby-bundler".to_string()],
+            };
+            packages.extend(
+                package_manager
+                    .build_essentials()
+                    .iter()
+                    .map(|p| p.to_string()),
+            );
+            return Ok(InstallPlan::packages(&packages));
+        }
+
+        Ok(source_plan(version, package_manager))
+    }
+}
+
+/// Compile the requested Ruby with ruby-build, which verifies the checksum of
+/// the source tarball itself. A minor version resolves to its newest patch release.
+fn source_plan(version: &str, package_manager: PackageManager) -> InstallPlan {
+    let mut packages = vec!["ca-certificates", "curl", "tar", "gzip"];
+    packages.extend(package_manager.build_essentials());
+    packages.extend(match package_manager {
+        PackageManager::Apt => &[
+            "libssl-dev",
+            "libyaml-dev",
+            "zlib1g-dev",
+            "libffi-dev",
+            "libreadline-dev",
+        ][..],
+        PackageManager::Apk => &[
+            "bash",
+            "make",
+            "linux-headers",
+            "openssl-dev",
+            "yaml-dev",
+            "zlib-dev",
+            "libffi-dev",
+            "readline-dev",
+        ][..],
+        PackageManager::Dnf => &[
+            "openssl-devel",
+            "libyaml-devel",
+            "zlib-devel",
+            "libffi-devel",
+            "readline-devel",
+        ][..],
+        PackageManager::Zypper => &[
+            "libopenssl-devel",
+            "libyaml-devel",
+            "zlib-devel",
+            "libffi-devel",
+            "readline-devel",
+        ][..],
+        PackageManager::Pacman => &["openssl", "libyaml", "zlib", "libffi", "readline"][..],
+    });
+
+    let resolve_version = if version.split('.').count() > 2 {
+        format!("RUBY_VERSION={}", version)
+    } else {
+        format!(
+            "RUBY_VERSION=$(ruby-build --definitions | grep -E '^{}\\.[0-9]+$' | tail -n 1)",
+            version.replace('.', "\\.")
+        )
+    };
+
+    InstallPlan::packages(&packages)
+        .step(format!(
+            "curl -fsSLo ruby-build.tar.gz https://github.com/rbenv/ruby-build/archive/refs/tags/v{}.tar.gz",
+            RUBY_BUILD_VERSION
+        ))
+        .step(format!(
+            "echo \"{}  ruby-build.tar.gz\" | sha256sum -c -",
+            RUBY_BUILD_SHA256
+        ))
+        .step("mkdir ruby-build && tar -xzf ruby-build.tar.gz -C ruby-build --strip-components=1")
+        .step("PREFIX=/usr/local ./ruby-build/install.sh")
+        .step("rm -rf ruby-build ruby-build.tar.gz")
+        .step(resolve_version)
+        .step("ruby-build \"$RUBY_VERSION\" /usr/local")
+        .step("gem install bundler --no-document")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::languages::testing::{language, render, target};
+
+    #[test]
+    fn test_ruby_distro_package_when_release_matches() {
+        let dockerfile = render(
+            &RubyInstaller,
+            &language("ruby", "3.1"),
+            &target("debian", "bookworm"),
+        );
+        assert!(dockerfile.contains(
+            "apt-get install -y --no-install-recommends ruby-full ruby-bundler build-essential"
+        ));
+        assert!(!dockerfile.contains("ruby-build"));
+    }
+
+    #[test]
+    fn test_ruby_alpine_package() {
+        let dockerfile = render(
+            &RubyInstaller,
+            &language("ruby", "3.3"),
+            &target("alpine", "3.20"),
+        );
+        assert!(dockerfile.contains("apk add ruby~3.3 ruby-dev ruby-bundler make gcc musl-dev"));
+    }
+
+    #[test]
+    fn test_ruby_source_build_for_other_versions() {
+        let dockerfile = render(
+            &RubyInstaller,
+            &language("ruby", "3.3"),
+            &target("ubuntu", "22.04"),
+        );
+        assert!(dockerfile.contains("ruby-build/archive/refs/tags/v20241225.tar.gz"));
+        assert!(dockerfile.contains(&format!(
+            "echo \"{}  ruby-build.tar.gz\" | sha256sum -c -",
+            RUBY_BUILD_SHA256
+        )));
+        assert!(dockerfile.contains("grep -E '^3\\.3\\.[0-9]+$'"));
+        assert!(dockerfile.contains("gem install bundler --no-document"));
+
+        let alpine = render(
+            &RubyInstaller,
+            &language("ruby", "3.2.6"),
+            &target("alpine", "3.20"),
+        );
+        assert!(alpine.contains("RUBY_VERSION=3.2.6"));
+        assert!(alpine.contains("bash make linux-headers openssl-dev"));
+    }
+
+    #[test]
+    fn test_ruby_rejects_invalid_versions() {
+        for version in ["3", "head", "3.x"] {
+            assert!(RubyInstaller
+                .install_plan(&language("ruby", version), &target("debian", "bookworm"))
+                .is_err());
+        }
+    }
+}
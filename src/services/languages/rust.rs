@@ -0,0 +1,240 @@
+use super::{is_numeric_version, unavailable, LanguageInstaller};
+use crate::models::config::Language;
+use crate::services::distro::TargetOs;
+use crate::services::dockerfile_generator::GeneratorError;
+use crate::services::package_manager::InstallPlan;
+
+pub struct RustInstaller;
+
+const RUSTUP_HOME: &str = "/usr/local/rustup";
+const CARGO_HOME: &str = "/usr/local/cargo";
+
+/// rustup components that can be requested in `options.components`
+const COMPONENTS: &[&str] = &[
+    "clippy",
+    "rustfmt",
+    "rust-src",
+    "rust-analyzer",
+    "rust-docs",
+    "llvm-tools",
+    "llvm-tools-preview",
+    "miri",
+];
+
+impl LanguageInstaller for RustInstaller {
+    fn name(&self) -> &'static str {
+        "rust"
+    }
+
+    fn cache_dirs(&self) -> &'static [&'static str] {
+        &["/usr/local/cargo/registry", "/usr/local/cargo/git"]
+    }
+
+    fn install_plan(
+        &self,
+        language: &Language,
+        target: &TargetOs,
+    ) -> Result<InstallPlan, GeneratorError> {
+        let toolchain = language.version.as_str();
+        if !is_toolchain(toolchain) {
+            return Err(unavailable(language, target));
+        }
+
+        let options = &language.options;
+        let invalid = options
+            .components
+            .iter()
+            .find(|c| !COMPONENTS.contains(&c.as_str()))
+            .or_else(|| options.targets.iter().find(|t| !is_target_triple(t)));
+        if let Some(invalid) = invalid {
+            return Err(GeneratorError::UnsupportedOption {
+                language: language.name.clone(),
+                value: invalid.clone(),
+            });
+        }
+
+        // rustup installs stable by default
+        let mut args = String::from("-y");
+        if toolchain != "stable" {
+            args.push_str(&format!(" --default-toolchain {}", toolchain));
+        }
+        if !options.components.is_empty() {
+            args.push_str(&format!(" --component {}", options.components.join(",")));
+        }
+        if !options.targets.is_empty() {
+            args.push_str(&format!(" --target {}", options.targets.join(",")));
+        }
+
+        let mut packages = vec!["ca-certificates", "curl"];
+        packages.extend(target.package_manager().build_essentials());
+
+        Ok(InstallPlan::packages(&packages)
+            .step("curl --proto '=https' --tlsv1.2 -sSf https://sh.rustup.rs -o rustup-init.sh")
+            .step(format!(
+                "RUSTUP_HOME={} CARGO_HOME={} sh rustup-init.sh {}",
+                RUSTUP_HOME, CARGO_HOME, args
+            ))
+            .step("rm rustup-init.sh")
+            // Shared toolchain that a non-root dev user can update and install into
+            .step(format!("chmod -R a+w {} {}", RUSTUP_HOME, CARGO_HOME))
+            .env("RUSTUP_HOME", RUSTUP_HOME)
+            .env("CARGO_HOME", CARGO_HOME)
+            .env("PATH", format!("{}/bin:${{PATH}}", CARGO_HOME)))
+    }
+}
+
+/// Accepts the toolchain specs rustup understands without a host triple:
+/// a channel (`stable`, `beta`, `nightly`), optionally dated
+/// (`nightly-2024-05-01`), or a release version (`1.75` / `1.75.0`).
+fn is_toolchain(spec: &str) -> bool {
+    let (channel, date) = match spec.split_once('-') {
+        Some((channel, date)) => (channel, Some(date)),
+        None => (spec, None),
+    };
+
+    match channel {
+        "stable" | "beta" | "nightly" => date.is_none_or(is_date),
+        _ => {
+            date.is_none()
+                && (2..=3).contains(&channel.split('.').count())
+                && is_numeric_version(channel)
+        }
+    }
+}
+
+fn is_date(date: &str) -> bool {
+    let parts: Vec<&str> = date.split('-').collect();
+    parts.len() == 3
+        && [4, 2, 2]
+            .iter()
+            .zip(&parts)
+            .all(|(len, part)| part.len() == *len && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn is_target_triple(target: &str) -> bool {
+    target.split('-').count() >= 2
+        && target.split('-').all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::config::LanguageOptions;
+    use crate::services::languages::testing::{language, language_with, render, target};
+
+    #[test]
+    fn test_rust_nightly_toolchain() {
+        let dockerfile = render(
+            &RustInstaller,
+            &language("rust", "nightly"),
+            &target("ubuntu", "22.04"),
+        );
+        assert!(dockerfile.contains("--default-toolchain nightly"));
+        assert!(dockerfile.contains("build-essential"));
+        assert!(dockerfile.contains("ENV PATH=\"/usr/local/cargo/bin:${PATH}\""));
+    }
+
+    #[test]
+    fn test_rust_alpine_build_tools() {
+        let dockerfile = render(
+            &RustInstaller,
+            &language("rust", "stable"),
+            &target("alpine", "latest"),
+        );
+        assert!(dockerfile.contains("apk add ca-certificates curl gcc musl-dev"));
+        assert!(!dockerfile.contains("--default-toolchain"));
+    }
+
+    #[test]
+    fn test_rust_arch_build_tools() {
+        let dockerfile = render(
+            &RustInstaller,
+            &language("rust", "stable"),
+            &target("arch", "latest"),
+        );
+        assert!(
+            dockerfile.contains("pacman -S --noconfirm --needed ca-certificates curl base-devel")
+        );
+    }
+
+    #[test]
+    fn test_rust_exact_toolchains() {
+        for toolchain in [
+            "1.75.0",
+            "1.75",
+            "beta",
+            "nightly-2024-05-01",
+            "beta-2024-04-30",
+        ] {
+            let dockerfile = render(
+                &RustInstaller,
+                &language("rust", toolchain),
+                &target("debian", "bookworm"),
+            );
+            assert!(
+                dockerfile.contains(&format!("--default-toolchain {}", toolchain)),
+                "{}",
+                dockerfile
+            );
+        }
+    }
+
+    #[test]
+    fn test_rust_components_and_targets() {
+        let options = LanguageOptions {
+            components: vec!["clippy".to_string(), "rust-src".to_string()],
+            targets: vec![
+                "wasm32-unknown-unknown".to_string(),
+                "x86_64-unknown-linux-musl".to_string(),
+            ],
+            ..Default::default()
+        };
+        let dockerfile = render(
+            &RustInstaller,
+            &language_with("rust", "1.75.0", options),
+            &target("debian", "bookworm"),
+        );
+        assert!(dockerfile.contains(
+            "sh rustup-init.sh -y --default-toolchain 1.75.0 --component clippy,rust-src \
+             --target wasm32-unknown-unknown,x86_64-unknown-linux-musl"
+        ));
+    }
+
+    #[test]
+    fn test_rust_rejects_invalid_specs() {
+        let target = target("debian", "bookworm");
+        for toolchain in [
+            "",
+            "latest",
+            "nightly-2024-5-1",
+            "1",
+            "1.75.0-beta",
+            "stable; id",
+        ] {
+            assert!(RustInstaller
+                .install_plan(&language("rust", toolchain), &target)
+                .is_err());
+        }
+
+        for options in [
+            LanguageOptions {
+                components: vec!["cargo-expand".to_string()],
+                ..Default::default()
+            },
+            LanguageOptions {
+                targets: vec!["wasm32 && id".to_string()],
+                ..Default::default()
+            },
+        ] {
+            let err = RustInstaller
+                .install_plan(&language_with("rust", "stable", options), &target)
+                .unwrap_err();
+            assert!(matches!(err, GeneratorError::UnsupportedOption { .. }));
+        }
+    }
+}
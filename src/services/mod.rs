@@ -1,2 +1,10 @@
+pub mod build_context;
+pub mod build_jobs;
+pub mod build_options;
+pub mod distro;
 pub mod docker_service;
 pub mod dockerfile_generator;
+pub mod dockerfile_linter;
+pub mod dockerfile_parser;
+pub mod languages;
+pub mod package_manager;
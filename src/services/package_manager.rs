@@ -0,0 +1,302 @@
+//! System package managers supported by the Dockerfile generator.
+
+use crate::models::dockerfile::{CacheMount, Instruction};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageManager {
+    Apt,
+    Apk,
+    Dnf,
+    Zypper,
+    Pacman,
+}
+
+impl PackageManager {
+    pub const ALL: [PackageManager; 5] = [
+        PackageManager::Apt,
+        PackageManager::Apk,
+        PackageManager::Dnf,
+        PackageManager::Zypper,
+        PackageManager::Pacman,
+    ];
+
+    /// Name used to pick per-manager package names in the configuration
+    pub fn name(self) -> &'static str {
+        match self {
+            PackageManager::Apt => "apt",
+            PackageManager::Apk => "apk",
+            PackageManager::Dnf => "dnf",
+            PackageManager::Zypper => "zypper",
+            PackageManager::Pacman => "pacman",
+        }
+    }
+
+    /// Command that refreshes the package index
+    pub fn update(self) -> &'static str {
+        match self {
+            PackageManager::Apt => "apt-get update",
+            PackageManager::Apk => "apk update",
+            PackageManager::Dnf => "dnf makecache",
+            PackageManager::Zypper => "zypper --non-interactive refresh",
+            PackageManager::Pacman => "pacman -Syu --noconfirm",
+        }
+    }
+
+    /// Command that installs the given packages non-interactively
+    pub fn install<S: AsRef<str>>(self, packages: &[S]) -> String {
+        let command = match self {
+            PackageManager::Apt => {
+                "DEBIAN_FRONTEND=noninteractive apt-get install -y --no-install-recommends"
+            }
+            PackageManager::Apk => "apk add",
+            PackageManager::Dnf => "dnf install -y",
+            PackageManager::Zypper => "zypper --non-interactive install",
+            PackageManager::Pacman => "pacman -S --noconfirm --needed",
+        };
+        let packages: Vec<&str> = packages.iter().map(|p| p.as_ref()).collect();
+        format!("{} {}", command, packages.join(" "))
+    }
+
+    /// Command that removes package index and download caches
+    pub fn cleanup(self) -> &'static str {
+        match self {
+            PackageManager::Apt => "rm -rf /var/lib/apt/lists/*",
+            PackageManager::Apk => "rm -rf /var/cache/apk/*",
+            PackageManager::Dnf => "dnf clean all",
+            PackageManager::Zypper => "zypper clean --all",
+            PackageManager::Pacman => "rm -rf /var/cache/pacman/pkg/*",
+        }
+    }
+
+    /// Compiler toolchain packages needed to build native code
+    pub fn build_essentials(self) -> &'static [&'static str] {
+        match self {
+            PackageManager::Apt => &["build-essential"],
+            PackageManager::Apk => &["gcc", "musl-dev"],
+            PackageManager::Dnf | PackageManager::Zypper => &["gcc", "gcc-c++", "make"],
+            PackageManager::Pacman => &["base-devel"],
+        }
+    }
+
+    /// Commands for a single RUN instruction that refreshes the index,
+    /// installs `packages`, runs `steps` and finally cleans up the caches.
+    /// Without packages the index is left alone and only `steps` are run,
+    /// followed by a cleanup if they refresh the index themselves. With
+    /// `cached` the caches live in BuildKit cache mounts and are kept.
+    pub fn run_install<S: AsRef<str>>(
+        self,
+        packages: &[S],
+        steps: &[String],
+        cached: bool,
+    ) -> Vec<String> {
+        let mut commands = Vec::new();
+        if !packages.is_empty() {
+            commands.push(self.update().to_string());
+            commands.push(self.install(packages));
+        }
+        commands.extend(steps.iter().filter(|s| *s != self.cleanup()).cloned());
+        if !cached && self.needs_index(packages, steps) {
+            commands.push(self.cleanup().to_string());
+        }
+        commands
+    }
+
+    /// Cache directories mounted into RUN instructions in BuildKit cache mode
+    pub fn cache_mounts(self) -> Vec<CacheMount> {
+        let targets: &[&str] = match self {
+            PackageManager::Apt => &["/var/cache/apt", "/var/lib/apt"],
+            // apk only keeps downloads when this directory exists
+            PackageManager::Apk => &["/etc/apk/cache"],
+            PackageManager::Dnf => &["/var/cache/dnf"],
+            PackageManager::Zypper => &["/var/cache/zypp"],
+            PackageManager::Pacman => &["/var/cache/pacman/pkg"],
+        };
+        // Package managers lock their caches, so concurrent builds take turns
+        targets.iter().map(|t| CacheMount::locked(*t)).collect()
+    }
+
+    /// Commands that stop the base image from discarding downloaded packages
+    pub fn keep_cache(self) -> &'static [&'static str] {
+        match self {
+            PackageManager::Apt => &[
+                "rm -f /etc/apt/apt.conf.d/docker-clean",
+                "echo 'Binary::apt::APT::Keep-Downloaded-Packages \"true\";' > /etc/apt/apt.conf.d/keep-cache",
+            ],
+            PackageManager::Dnf => &["echo keepcache=True >> /etc/dnf/dnf.conf"],
+            PackageManager::Zypper => &["zypper --non-interactive modifyrepo --all --keep-packages"],
+            PackageManager::Apk | PackageManager::Pacman => &[],
+        }
+    }
+
+    fn needs_index<S: AsRef<str>>(self, packages: &[S], steps: &[String]) -> bool {
+        !packages.is_empty() || steps.iter().any(|s| self.uses_index(s))
+    }
+
+    /// Whether `command` refreshes the index or installs from it
+    fn uses_index(self, command: &str) -> bool {
+        command == self.update() || command.starts_with(&self.install::<&str>(&[]))
+    }
+}
+
+/// What an installer needs from the base image: distro packages, shell steps
+/// run once those packages are present, and environment variables to set.
+#[derive(Debug, Default)]
+pub struct InstallPlan {
+    pub packages: Vec<String>,
+    pub steps: Vec<String>,
+    pub env: Vec<(String, String)>,
+}
+
+impl InstallPlan {
+    pub fn packages<S: AsRef<str>>(packages: &[S]) -> Self {
+        Self {
+            packages: packages.iter().map(|p| p.as_ref().to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn step(mut self, step: impl Into<String>) -> Self {
+        self.steps.push(step.into());
+        self
+    }
+
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// Instructions for this plan: one RUN layer followed by ENV instructions.
+    /// With `cached` the package manager's caches are mounted into the RUN.
+    pub fn instructions(&self, package_manager: PackageManager, cached: bool) -> Vec<Instruction> {
+        let commands = package_manager.run_install(&self.packages, &self.steps, cached);
+        let mounts = if cached && package_manager.needs_index(&self.packages, &self.steps) {
+            package_manager.cache_mounts()
+        } else {
+            Vec::new()
+        };
+        let run = (!commands.is_empty()).then_some(Instruction::Run { mounts, commands });
+        run.into_iter()
+            .chain(
+                self.env
+                    .iter()
+                    .map(|(key, value)| Instruction::Env(key.clone(), value.clone())),
+            )
+            .collect()
+    }
+}
+
+/// Move the distro packages of all `plans` into one list, in order of
+/// first appearance, so they can be installed in a single layer ahead of
+/// the plans' own steps
+pub fn merge_packages<'a>(plans: impl IntoIterator<Item = &'a mut InstallPlan>) -> Vec<String> {
+    let mut packages: Vec<String> = Vec::new();
+    for plan in plans {
+        for package in std::mem::take(&mut plan.packages) {
+            if !packages.contains(&package) {
+                packages.push(package);
+            }
+        }
+    }
+    packages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_commands() {
+        assert_eq!(
+            PackageManager::Apk.install(&["curl", "git"]),
+            "apk add curl git"
+        );
+        assert_eq!(
+            PackageManager::Pacman.install(&["curl"]),
+            "pacman -S --noconfirm --needed curl"
+        );
+        assert_eq!(
+            PackageManager::Zypper.install(&["curl"]),
+            "zypper --non-interactive install curl"
+        );
+    }
+
+    #[test]
+    fn test_run_install_layout() {
+        let commands =
+            PackageManager::Dnf.run_install(&["gcc"], &["gcc --version".to_string()], false);
+        assert_eq!(
+            commands,
+            vec![
+                "dnf makecache",
+                "dnf install -y gcc",
+                "gcc --version",
+                "dnf clean all",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_install_plan_instructions() {
+        let plan = InstallPlan::packages(&["curl"])
+            .step("curl --version")
+            .env("PATH", "/opt/bin:${PATH}");
+        let rendered: Vec<String> = plan
+            .instructions(PackageManager::Apk, false)
+            .iter()
+            .map(|i| i.to_string())
+            .collect();
+        assert_eq!(
+            rendered.join("\n"),
+            "RUN apk update && \\\n    apk add curl && \\\n    curl --version && \\\n    \
+             rm -rf /var/cache/apk/*\nENV PATH=\"/opt/bin:${PATH}\""
+        );
+    }
+
+    #[test]
+    fn test_merge_packages() {
+        let mut rust = InstallPlan::packages(&["ca-certificates", "curl", "build-essential"])
+            .step("sh rustup-init.sh");
+        let mut node = InstallPlan::packages(&["ca-certificates", "curl"])
+            .step("bash nodesource_setup.sh")
+            .step(PackageManager::Apt.install(&["nodejs"]));
+
+        let packages = merge_packages([&mut rust, &mut node]);
+        assert_eq!(packages, ["ca-certificates", "curl", "build-essential"]);
+        assert!(rust.packages.is_empty() && node.packages.is_empty());
+
+        // Steps that install from a freshly added repository still clean up after themselves
+        let commands = PackageManager::Apt.run_install::<&str>(&[], &node.steps, false);
+        assert_eq!(commands.last().unwrap(), "rm -rf /var/lib/apt/lists/*");
+        let commands = PackageManager::Apt.run_install::<&str>(&[], &rust.steps, false);
+        assert_eq!(commands, ["sh rustup-init.sh"]);
+    }
+
+    #[test]
+    fn test_cached_install_keeps_caches() {
+        let plan = InstallPlan::packages(&["curl"])
+            .step("curl --version")
+            .step(PackageManager::Dnf.cleanup());
+        let instructions = plan.instructions(PackageManager::Dnf, true);
+        assert_eq!(
+            instructions[0].to_string(),
+            "RUN --mount=type=cache,target=/var/cache/dnf,sharing=locked \\\n    \
+             dnf makecache && \\\n    dnf install -y curl && \\\n    curl --version"
+        );
+
+        // Plans that don't touch the package manager get no mounts
+        let plan = InstallPlan::default().step("id -u");
+        assert_eq!(
+            plan.instructions(PackageManager::Apt, true)[0].to_string(),
+            "RUN id -u"
+        );
+    }
+
+    #[test]
+    fn test_run_install_without_packages() {
+        let commands = PackageManager::Apt.run_install::<&str>(&[], &["id -u".to_string()], false);
+        assert_eq!(commands, vec!["id -u"]);
+        assert!(InstallPlan::default()
+            .instructions(PackageManager::Apt, false)
+            .is_empty());
+    }
+}